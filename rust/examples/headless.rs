@@ -0,0 +1,41 @@
+use std::env;
+
+use log::LevelFilter;
+
+use dungeon_demons::{vec2, Game};
+
+const TICK_DELTA: f64 = 1.0 / 60.0;
+const REPORT_INTERVAL: usize = 600;
+
+fn main() {
+    dungeon_demons::start();
+    log::set_max_level(LevelFilter::Info);
+
+    let mut args = env::args().skip(1);
+    let seed = args.next().and_then(|arg| arg.parse().ok()).unwrap_or(0);
+    let seconds: f64 = args.next().and_then(|arg| arg.parse().ok()).unwrap_or(60.0);
+
+    let mut game = Game::new(seed, false);
+    game.enable_hud();
+    game.inputs(0).unwrap().mouse_down(vec2(1.0, 0.0));
+
+    let ticks = (seconds / TICK_DELTA).ceil() as usize;
+
+    for tick in 0..ticks {
+        game.tick(TICK_DELTA);
+
+        if tick % REPORT_INTERVAL == 0 || game.is_over() {
+            log::info!(
+                "t={:.1}s round={} credits={} entities={}",
+                tick as f64 * TICK_DELTA,
+                game.round() + 1,
+                game.credits(0).unwrap_or(0),
+                game.entity_count(),
+            );
+        }
+
+        if game.is_over() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use std::env;
+
+use dungeon_demons::{coord, MapSnapshot};
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let seed = args.next().and_then(|arg| arg.parse().ok()).unwrap_or(0);
+    let radius: i32 = args.next().and_then(|arg| arg.parse().ok()).unwrap_or(25);
+    let json = args.next().as_deref() == Some("--json");
+
+    let snapshot = MapSnapshot::generate(seed, coord(-radius, -radius), coord(radius, radius));
+
+    if json {
+        println!("{}", snapshot.to_json());
+    } else {
+        print!("{}", snapshot.to_ascii());
+    }
+}
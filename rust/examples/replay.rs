@@ -0,0 +1,41 @@
+use std::{env, fs, process};
+
+use log::LevelFilter;
+
+use dungeon_demons::replay::{Recording, Replayer};
+
+fn main() {
+    dungeon_demons::start();
+    log::set_max_level(LevelFilter::Info);
+
+    let path = match env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: replay <recording.json>");
+            process::exit(1);
+        }
+    };
+
+    let json = fs::read_to_string(&path).unwrap();
+    let recording = Recording::from_json(&json).unwrap();
+
+    log::info!(
+        "Replaying {} ticks with seed {}",
+        recording.tick_count(),
+        recording.seed()
+    );
+
+    let mut replayer = Replayer::new(recording);
+    replayer.run_to_end();
+
+    let game = replayer.game();
+
+    log::info!(
+        "Finished after {} ticks: round={} credits={} entities={} over={}",
+        replayer.ticks(),
+        game.round() + 1,
+        game.credits(0).unwrap_or(0),
+        game.entity_count(),
+        game.is_over(),
+    );
+}
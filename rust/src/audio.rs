@@ -1,10 +1,4 @@
-use wasm_bindgen::prelude::*;
-
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = window, js_name = dungeonDemonsPlaySound)]
-    fn dispatch(name: &str);
-}
+use crate::platform;
 
 #[derive(Copy, Clone)]
 pub struct Sound {
@@ -14,13 +8,13 @@ pub struct Sound {
 impl Sound {
     fn new(name: &'static str) -> Self {
         Self {
-            name: wasm_bindgen::intern(name),
+            name: platform::intern(name),
         }
     }
 }
 
 pub fn play_sound(sound: Sound) {
-    dispatch(sound.name);
+    platform::play_sound(sound.name);
 }
 
 pub mod sound {
@@ -44,12 +38,16 @@ pub mod sound {
     sound!(shoot_fast);
     sound!(shoot_sniper);
     sound!(shoot_shotgun);
+    sound!(explosion);
     sound!(hit);
     sound!(kill);
     sound!(player_hit);
     sound!(death);
     sound!(round_start);
+    sound!(boss_round_start);
     sound!(round_end);
     sound!(purchase);
     sound!(pickup);
+    sound!(dash);
+    sound!(revive);
 }
@@ -0,0 +1,323 @@
+use std::f64::consts::PI;
+
+use float_ord::FloatOrd;
+use specs::prelude::*;
+use specs::{Component, Entities, LazyUpdate, ReadExpect, ReadStorage, System, WriteStorage};
+
+use crate::audio::{play_sound, sound};
+use crate::components::bounds::Bounds;
+use crate::components::health::Health;
+use crate::components::physics::Physics;
+use crate::components::player::Player;
+use crate::entities::demon::{self, DemonKind};
+use crate::entities::explosion;
+use crate::game::Delta;
+use crate::map::Map;
+use crate::progression::Progression;
+use crate::util::vector::Vec2;
+
+const ATTACK_INTERVAL: f64 = 2.0;
+
+const CHARGE_WINDUP: f64 = 0.5;
+const CHARGE_DURATION: f64 = 0.6;
+const CHARGE_SPEED: f64 = 9.0;
+
+const SUMMON_WINDUP: f64 = 0.8;
+const SUMMONED_MINIONS: usize = 3;
+const SUMMON_DISTANCE: f64 = 0.8;
+
+const SLAM_WINDUP: f64 = 0.9;
+const SLAM_RADIUS: f64 = 2.5;
+const SLAM_DAMAGE: f64 = 40.0;
+const SLAM_KNOCKBACK: f64 = 12.0;
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum BossAttack {
+    Charge,
+    Summon,
+    Slam,
+}
+
+impl BossAttack {
+    fn windup(self) -> f64 {
+        match self {
+            BossAttack::Charge => CHARGE_WINDUP,
+            BossAttack::Summon => SUMMON_WINDUP,
+            BossAttack::Slam => SLAM_WINDUP,
+        }
+    }
+}
+
+/// The attacks cycled through in each phase, as the boss loses health.
+const PHASE_ATTACKS: [&[BossAttack]; 3] = [
+    &[BossAttack::Charge],
+    &[BossAttack::Charge, BossAttack::Summon],
+    &[BossAttack::Charge, BossAttack::Summon, BossAttack::Slam],
+];
+
+#[derive(Copy, Clone)]
+enum BossAction {
+    Idle,
+    WindingUp {
+        attack: BossAttack,
+        remaining_time: f64,
+        target: Vec2,
+    },
+    Charging {
+        remaining_time: f64,
+        direction: Vec2,
+    },
+}
+
+#[derive(Clone)]
+pub struct Boss {
+    time_until_attack: f64,
+    attack_index: usize,
+    action: BossAction,
+}
+
+impl Boss {
+    pub fn new() -> Self {
+        Self {
+            time_until_attack: ATTACK_INTERVAL,
+            attack_index: 0,
+            action: BossAction::Idle,
+        }
+    }
+
+    /// Whether an attack is in progress, during which the boss doesn't seek players.
+    pub fn is_attacking(&self) -> bool {
+        !matches!(self.action, BossAction::Idle)
+    }
+
+    fn phase(health: &Health) -> usize {
+        let lost = 1.0 - health.remaining_relative();
+        ((lost * PHASE_ATTACKS.len() as f64) as usize).min(PHASE_ATTACKS.len() - 1)
+    }
+
+    fn next_attack(&mut self, health: &Health) -> BossAttack {
+        let attacks = PHASE_ATTACKS[Self::phase(health)];
+        let attack = attacks[self.attack_index % attacks.len()];
+        self.attack_index += 1;
+        attack
+    }
+
+    fn finish_attack(&mut self) {
+        self.action = BossAction::Idle;
+        self.time_until_attack = ATTACK_INTERVAL;
+    }
+}
+
+impl Component for Boss {
+    type Storage = HashMapStorage<Self>;
+}
+
+pub struct UpdateBosses;
+
+impl UpdateBosses {
+    fn closest_player(position: Vec2, players: &[Vec2]) -> Option<Vec2> {
+        players
+            .iter()
+            .copied()
+            .min_by_key(|player| FloatOrd(player.distance(position)))
+    }
+
+    fn start_attack(data: &UpdateBossesData, attack: BossAttack, position: Vec2) {
+        let size = match attack {
+            BossAttack::Charge => Vec2::one() * 0.6,
+            BossAttack::Summon => Vec2::one() * SUMMON_DISTANCE * 2.0,
+            BossAttack::Slam => Vec2::one() * SLAM_RADIUS * 2.0,
+        };
+
+        let duration = attack.windup();
+        data.lazy_update.exec_mut(move |world| {
+            demon::create_charge_sized(world, position, size, duration);
+        });
+    }
+
+    fn summon(data: &mut UpdateBossesData, position: Vec2) {
+        let health = data.progression.minion_health();
+        let speed = data.progression.enemy_speed();
+
+        let spawns = (0..SUMMONED_MINIONS)
+            .map(|index| {
+                let angle = index as f64 / SUMMONED_MINIONS as f64 * 2.0 * PI;
+                position + Vec2::right().rotate(angle) * SUMMON_DISTANCE
+            })
+            .map(|spawn| {
+                if data.map.at(spawn.coord()).is_walkable() {
+                    spawn
+                } else {
+                    position
+                }
+            })
+            .collect::<Vec<_>>();
+
+        data.progression.report_enemies_added(spawns.len());
+
+        data.lazy_update.exec_mut(move |world| {
+            for spawn in spawns {
+                demon::create_spawning(world, DemonKind::Melee, spawn, health, speed, None);
+            }
+        });
+    }
+
+    fn slam(data: &mut UpdateBossesData, position: Vec2) {
+        play_sound(sound::explosion());
+
+        let iter = (
+            &data.bounds,
+            &mut data.healths,
+            &mut data.physicses,
+            &data.players,
+        )
+            .join();
+
+        for (bounds, health, physics, player) in iter {
+            if player.is_invulnerable() {
+                continue;
+            }
+
+            let offset = bounds.0.center() - position;
+            let falloff = 1.0 - offset.length() / SLAM_RADIUS;
+
+            if falloff <= 0.0 {
+                continue;
+            }
+
+            health.damage(SLAM_DAMAGE * falloff);
+            *physics.velocity_mut() += offset.normalize_or_zero() * SLAM_KNOCKBACK * falloff;
+
+            play_sound(sound::player_hit());
+            if health.is_dead() {
+                play_sound(sound::death());
+            }
+        }
+
+        data.lazy_update.exec_mut(move |world| {
+            explosion::create(world, position, SLAM_RADIUS);
+        });
+    }
+}
+
+#[derive(SystemData)]
+pub struct UpdateBossesData<'a> {
+    entities: Entities<'a>,
+    lazy_update: ReadExpect<'a, LazyUpdate>,
+    map: ReadExpect<'a, Map>,
+    delta: ReadExpect<'a, Delta>,
+    progression: WriteExpect<'a, Progression>,
+    bounds: ReadStorage<'a, Bounds>,
+    healths: WriteStorage<'a, Health>,
+    physicses: WriteStorage<'a, Physics>,
+    players: ReadStorage<'a, Player>,
+    bosses: WriteStorage<'a, Boss>,
+}
+
+impl<'a> System<'a> for UpdateBosses {
+    type SystemData = UpdateBossesData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        let player_positions = (&data.bounds, &data.players)
+            .join()
+            .filter(|(_, player)| !player.is_down())
+            .map(|(bounds, _)| bounds.0.center())
+            .collect::<Vec<_>>();
+
+        let boss_entities = (&data.entities, &data.bosses)
+            .join()
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+
+        for entity in boss_entities {
+            let position = data.bounds.get(entity).unwrap().0.center();
+            let health = *data.healths.get(entity).unwrap();
+            let mut boss = data.bosses.get(entity).unwrap().clone();
+
+            match &mut boss.action {
+                BossAction::Idle => {
+                    boss.time_until_attack -= data.delta.0;
+
+                    let target = Self::closest_player(position, &player_positions);
+
+                    if let (true, Some(target)) = (boss.time_until_attack <= 0.0, target) {
+                        let attack = boss.next_attack(&health);
+
+                        boss.action = BossAction::WindingUp {
+                            attack,
+                            remaining_time: attack.windup(),
+                            target,
+                        };
+
+                        Self::start_attack(&data, attack, position);
+                    }
+                }
+                BossAction::WindingUp {
+                    attack,
+                    remaining_time,
+                    target,
+                } => {
+                    *remaining_time -= data.delta.0;
+
+                    if *remaining_time <= 0.0 {
+                        match *attack {
+                            BossAttack::Charge => {
+                                boss.action = BossAction::Charging {
+                                    remaining_time: CHARGE_DURATION,
+                                    direction: (*target - position).normalize_or_zero(),
+                                };
+                            }
+                            BossAttack::Summon => {
+                                Self::summon(&mut data, position);
+                                boss.finish_attack();
+                            }
+                            BossAttack::Slam => {
+                                Self::slam(&mut data, position);
+                                boss.finish_attack();
+                            }
+                        }
+                    }
+                }
+                BossAction::Charging {
+                    remaining_time,
+                    direction,
+                } => {
+                    *remaining_time -= data.delta.0;
+
+                    let physics = data.physicses.get_mut(entity).unwrap();
+                    *physics.velocity_mut() = *direction * CHARGE_SPEED;
+
+                    if *remaining_time <= 0.0 {
+                        boss.finish_attack();
+                    }
+                }
+            }
+
+            *data.bosses.get_mut(entity).unwrap() = boss;
+        }
+    }
+}
+
+pub struct DropBossLoot;
+
+impl<'a> System<'a> for DropBossLoot {
+    type SystemData = (
+        ReadExpect<'a, LazyUpdate>,
+        ReadStorage<'a, Bounds>,
+        ReadStorage<'a, Health>,
+        ReadStorage<'a, Boss>,
+    );
+
+    fn run(&mut self, (lazy_update, bounds, healths, bosses): Self::SystemData) {
+        for (bounds, health, _) in (&bounds, &healths, &bosses).join() {
+            if !health.is_dead() {
+                continue;
+            }
+
+            let position = bounds.0.center();
+            lazy_update.exec_mut(move |world| {
+                world.fetch_mut::<Map>().drop_chest(position).open(world);
+            });
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use specs::prelude::*;
 use specs::{Component, VecStorage};
 
 use crate::util::rect::Rect;
@@ -8,3 +9,27 @@ pub struct Bounds(pub Rect);
 impl Component for Bounds {
     type Storage = VecStorage<Bounds>;
 }
+
+/// The bounds at the start of the current tick, used to interpolate rendering between ticks.
+#[derive(Copy, Clone)]
+pub struct PreviousBounds(pub Rect);
+
+impl Component for PreviousBounds {
+    type Storage = VecStorage<PreviousBounds>;
+}
+
+pub struct RecordPreviousBounds;
+
+impl<'a> System<'a> for RecordPreviousBounds {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Bounds>,
+        WriteStorage<'a, PreviousBounds>,
+    );
+
+    fn run(&mut self, (entities, bounds, mut previous_bounds): Self::SystemData) {
+        for (entity, bounds) in (&entities, &bounds).join() {
+            let _ = previous_bounds.insert(entity, PreviousBounds(bounds.0));
+        }
+    }
+}
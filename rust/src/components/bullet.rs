@@ -1,28 +1,72 @@
 use crate::audio::{play_sound, sound};
+use crate::components::bounds::Bounds;
+use crate::components::elite::Elite;
 use crate::components::health::Health;
 use crate::components::physics::Physics;
 use crate::components::player::Player;
+use crate::components::status_effects::{StatusEffect, StatusEffects};
+use crate::entities::explosion;
 use crate::progression::Progression;
+use crate::util::intersection_grid::IntersectionGrid;
+use crate::util::rect::Rect;
 use crate::util::vector::Vec2;
 use specs::prelude::*;
-use specs::{Component, NullStorage, ReadStorage, System, WriteStorage};
+use specs::{Component, LazyUpdate, NullStorage, ReadStorage, System, WriteStorage};
 
 const KILL_CREDITS: usize = 100;
 const HIT_CREDITS: usize = 10;
 
+/// The side a bullet was fired by, which decides what it can hit.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum Faction {
+    Players,
+    Enemies,
+}
+
 #[derive(Copy, Clone)]
 pub struct Bullet {
     owner: Entity,
+    faction: Faction,
     damage: f64,
     knockback: f64,
+    explosion_radius: Option<f64>,
+    effect: Option<StatusEffect>,
 }
 
 impl Bullet {
-    pub fn new(owner: Entity, damage: f64, knockback: f64) -> Self {
+    pub fn new(owner: Entity, damage: f64, knockback: f64, explosion_radius: Option<f64>) -> Self {
+        Self {
+            owner,
+            faction: Faction::Players,
+            damage,
+            knockback,
+            explosion_radius,
+            effect: None,
+        }
+    }
+
+    /// A bullet fired by an enemy, hitting players instead of `BulletTarget`s.
+    pub fn hostile(owner: Entity, damage: f64, knockback: f64) -> Self {
         Self {
             owner,
+            faction: Faction::Enemies,
             damage,
             knockback,
+            explosion_radius: None,
+            effect: None,
+        }
+    }
+
+    /// Makes the bullet inflict `effect` on whatever it hits.
+    pub fn with_effect(self, effect: Option<StatusEffect>) -> Self {
+        Self { effect, ..self }
+    }
+
+    fn scaled(self, factor: f64) -> Self {
+        Self {
+            damage: self.damage * factor,
+            knockback: self.knockback * factor,
+            ..self
         }
     }
 }
@@ -38,23 +82,195 @@ impl Component for BulletTarget {
     type Storage = NullStorage<Self>;
 }
 
-pub struct UpdateBullets;
+/// A bullet reaching a wall or a target during this tick.
+struct Impact {
+    bullet: Entity,
+    target: Option<Entity>,
+    position: Vec2,
+}
+
+type Targets = IntersectionGrid<Entity, ()>;
+
+/// The storages touched when a bullet or beam hits a target.
+#[derive(SystemData)]
+pub struct HitData<'a> {
+    progression: WriteExpect<'a, Progression>,
+    healths: WriteStorage<'a, Health>,
+    physicses: WriteStorage<'a, Physics>,
+    players: WriteStorage<'a, Player>,
+    elites: ReadStorage<'a, Elite>,
+    pub(super) status_effects: WriteStorage<'a, StatusEffects>,
+}
+
+impl<'a> HitData<'a> {
+    pub fn is_dead(&self, entity: Entity) -> bool {
+        self.healths
+            .get(entity)
+            .map_or(false, |health| health.is_dead())
+    }
+
+    /// Damages and knocks back the target, returning how much of its health was taken.
+    pub fn apply_hit(&mut self, bullet: Bullet, direction: Vec2, target_entity: Entity) -> f64 {
+        let bullet = Bullet {
+            damage: self.scale_damage(bullet.damage, target_entity),
+            ..bullet
+        };
+        let elite = self.elites.get(target_entity);
+
+        if let Some(physics) = self.physicses.get_mut(target_entity) {
+            Self::knockback(bullet, direction, physics);
+        }
+
+        let mut dealt_damage = bullet.damage;
+
+        if let Some(health) = self.healths.get_mut(target_entity) {
+            dealt_damage = dealt_damage.min(health.remaining_absolute());
+            match bullet.faction {
+                Faction::Players => Self::damage(
+                    bullet,
+                    health,
+                    elite,
+                    &mut self.progression,
+                    &mut self.players,
+                ),
+                Faction::Enemies => Self::damage_player(bullet, health),
+            }
+        }
+
+        if bullet.faction == Faction::Enemies {
+            self.drain(bullet.owner, dealt_damage);
+        }
+
+        if let Some(effect) = bullet.effect {
+            if !self.is_dead(target_entity) {
+                StatusEffects::inflict(
+                    &mut self.status_effects,
+                    target_entity,
+                    effect,
+                    bullet.owner,
+                    bullet.faction,
+                );
+            }
+        }
+
+        dealt_damage
+    }
+
+    /// Deals a tick of damage from a burn or poison inflicted by `source`. Unlike hits, these
+    /// only award credits for the kill.
+    pub fn apply_damage_over_time(
+        &mut self,
+        source: Entity,
+        faction: Faction,
+        damage: f64,
+        target_entity: Entity,
+    ) {
+        let scaled_damage = self.scale_damage(damage, target_entity);
+
+        let health = match self.healths.get_mut(target_entity) {
+            Some(health) => health,
+            None => return,
+        };
+
+        match faction {
+            Faction::Players => {
+                Self::damage_enemy(
+                    source,
+                    scaled_damage,
+                    health,
+                    self.elites.get(target_entity),
+                    &mut self.progression,
+                    &mut self.players,
+                );
+            }
+            Faction::Enemies => {
+                let invulnerable = self
+                    .players
+                    .get(target_entity)
+                    .map_or(false, Player::is_invulnerable);
+
+                if invulnerable {
+                    return;
+                }
+
+                health.damage(damage);
+
+                if health.is_dead() {
+                    play_sound(sound::death());
+                }
+            }
+        }
+    }
 
-impl UpdateBullets {
     fn damage(
         bullet: Bullet,
         target_health: &mut Health,
+        target_elite: Option<&Elite>,
         progression: &mut Progression,
         players: &mut WriteStorage<Player>,
     ) {
-        target_health.damage(bullet.damage);
+        let killed = Self::damage_enemy(
+            bullet.owner,
+            bullet.damage,
+            target_health,
+            target_elite,
+            progression,
+            players,
+        );
 
-        if target_health.is_dead() {
+        if !killed {
+            let bonus = target_elite.map_or(0, |elite| elite.bonus_credits(false));
+            Self::give_credits(bullet.owner, HIT_CREDITS + bonus, players);
+        }
+    }
+
+    /// Damages an enemy on behalf of the player `owner`, crediting them if it was a kill. Returns
+    /// whether the enemy died.
+    fn damage_enemy(
+        owner: Entity,
+        damage: f64,
+        target_health: &mut Health,
+        target_elite: Option<&Elite>,
+        progression: &mut Progression,
+        players: &mut WriteStorage<Player>,
+    ) -> bool {
+        target_health.damage(damage);
+
+        let killed = target_health.is_dead();
+        if killed {
             play_sound(sound::kill());
             progression.report_enemy_killed();
-            Self::give_credits(bullet.owner, KILL_CREDITS, players);
-        } else {
-            Self::give_credits(bullet.owner, HIT_CREDITS, players);
+
+            let bonus = target_elite.map_or(0, |elite| elite.bonus_credits(true));
+            Self::give_credits(owner, KILL_CREDITS + bonus, players);
+        }
+
+        killed
+    }
+
+    /// Scales `damage` dealt to `target` by its elite affixes.
+    fn scale_damage(&self, damage: f64, target: Entity) -> f64 {
+        damage * self.elites.get(target).map_or(1.0, Elite::damage_factor)
+    }
+
+    /// Heals a vampiric elite for the damage its bullet dealt to a player.
+    fn drain(&mut self, owner: Entity, dealt_damage: f64) {
+        let elite = self.elites.get(owner);
+        let health = self.healths.get_mut(owner);
+
+        if let (Some(elite), Some(health)) = (elite, health) {
+            if !health.is_dead() {
+                health.heal(elite.drain(dealt_damage));
+            }
+        }
+    }
+
+    fn damage_player(bullet: Bullet, player_health: &mut Health) {
+        player_health.damage(bullet.damage);
+
+        play_sound(sound::player_hit());
+        if player_health.is_dead() {
+            play_sound(sound::death());
         }
     }
 
@@ -64,81 +280,180 @@ impl UpdateBullets {
         }
     }
 
-    fn knockback(bullet: Bullet, bullet_velocity: Vec2, target_physics: &mut Physics) {
-        let knockback = bullet_velocity.normalize_or_zero() * bullet.knockback;
+    fn knockback(bullet: Bullet, direction: Vec2, target_physics: &mut Physics) {
+        let knockback = direction.normalize_or_zero() * bullet.knockback;
         *target_physics.velocity_mut() += knockback;
     }
 }
 
-#[derive(SystemData)]
-pub struct UpdateBulletsData<'a> {
-    entities: Entities<'a>,
-    progression: WriteExpect<'a, Progression>,
-    healths: WriteStorage<'a, Health>,
-    physicses: WriteStorage<'a, Physics>,
-    bullets: ReadStorage<'a, Bullet>,
-    targets: ReadStorage<'a, BulletTarget>,
-    players: WriteStorage<'a, Player>,
-}
-
-impl<'a> System<'a> for UpdateBullets {
-    type SystemData = UpdateBulletsData<'a>;
+pub struct UpdateBullets;
 
-    fn run(&mut self, mut data: Self::SystemData) {
-        let mut hits = Vec::new();
+impl UpdateBullets {
+    fn find_impacts(data: &mut UpdateBulletsData) -> Vec<Impact> {
+        let mut impacts = Vec::new();
 
         let iter = (
             &data.entities,
-            &data.physicses,
-            &mut data.healths,
+            &data.hits.physicses,
+            &mut data.hits.healths,
             &data.bullets,
+            &data.bounds,
         )
             .join();
-        for (bullet_entity, bullet_physics, bullet_health, _) in iter {
+        for (bullet_entity, bullet_physics, bullet_health, bullet, bounds) in iter {
+            let position = bounds.0.center();
+
             if bullet_physics.collisions().hit_wall().unwrap() {
-                bullet_health.kill();
+                if bullet.explosion_radius.is_some() {
+                    impacts.push(Impact {
+                        bullet: bullet_entity,
+                        target: None,
+                        position,
+                    });
+                } else {
+                    bullet_health.kill();
+                }
+
                 continue;
             }
 
             for entity in bullet_physics.collisions().hit_entities().unwrap() {
-                if data.targets.contains(entity) {
-                    hits.push((bullet_entity, entity));
+                let is_target = match bullet.faction {
+                    Faction::Players => data.targets.contains(entity),
+                    Faction::Enemies => data
+                        .hits
+                        .players
+                        .get(entity)
+                        .map_or(false, |player| !player.is_invulnerable()),
+                };
+
+                if is_target {
+                    impacts.push(Impact {
+                        bullet: bullet_entity,
+                        target: Some(entity),
+                        position,
+                    });
                     break;
                 }
             }
         }
 
-        for (bullet_entity, target_entity) in hits {
-            if let Some(health) = data.healths.get(bullet_entity) {
-                if health.is_dead() {
-                    continue;
-                }
-            }
+        impacts
+    }
 
-            if let Some(health) = data.healths.get(target_entity) {
-                if health.is_dead() {
-                    continue;
-                }
-            }
+    fn create_targets(data: &UpdateBulletsData) -> Targets {
+        let mut grid = IntersectionGrid::new();
 
+        let iter = (&data.entities, &data.bounds, &data.targets).join();
+        for (entity, bounds, _) in iter {
+            grid.insert(bounds.0, entity, ());
+        }
+
+        grid
+    }
+
+    fn hit(data: &mut UpdateBulletsData, bullet_entity: Entity, target_entity: Entity) {
+        let bullet = *data.bullets.get(bullet_entity).unwrap();
+
+        if bullet.faction == Faction::Players {
             play_sound(sound::hit());
+        }
+        let velocity = data.hits.physicses.get(bullet_entity).unwrap().velocity();
 
-            let bullet = *data.bullets.get(bullet_entity).unwrap();
-            let velocity = data.physicses.get(bullet_entity).unwrap().velocity();
+        let dealt_damage = data.hits.apply_hit(bullet, velocity, target_entity);
 
-            if let Some(physics) = data.physicses.get_mut(target_entity) {
-                Self::knockback(bullet, velocity, physics);
+        if let Some(health) = data.hits.healths.get_mut(bullet_entity) {
+            health.damage(dealt_damage * 1.01);
+        }
+    }
+
+    fn explode(data: &mut UpdateBulletsData, targets: &Targets, impact: &Impact, radius: f64) {
+        play_sound(sound::explosion());
+
+        let bullet = *data.bullets.get(impact.bullet).unwrap();
+
+        if let Some(health) = data.hits.healths.get_mut(impact.bullet) {
+            health.kill();
+        }
+
+        let area = Rect::focused(impact.position, Vec2::one() * radius * 2.0);
+
+        let mut caught = targets
+            .query(area)
+            .into_iter()
+            .map(|(entity, (bounds, _))| (*entity, bounds.center()))
+            .collect::<Vec<_>>();
+
+        caught.sort_by_key(|(entity, _)| entity.id());
+
+        for (target_entity, center) in caught {
+            if data.hits.is_dead(target_entity) {
+                continue;
+            }
+
+            let offset = center - impact.position;
+            let falloff = 1.0 - offset.length() / radius;
+
+            if falloff <= 0.0 {
+                continue;
             }
 
-            let mut dealt_damage = bullet.damage;
+            data.hits
+                .apply_hit(bullet.scaled(falloff), offset, target_entity);
+        }
+
+        let position = impact.position;
+        data.lazy_update.exec_mut(move |world| {
+            explosion::create(world, position, radius);
+        });
+    }
+}
+
+#[derive(SystemData)]
+pub struct UpdateBulletsData<'a> {
+    entities: Entities<'a>,
+    lazy_update: ReadExpect<'a, LazyUpdate>,
+    hits: HitData<'a>,
+    bounds: ReadStorage<'a, Bounds>,
+    bullets: ReadStorage<'a, Bullet>,
+    targets: ReadStorage<'a, BulletTarget>,
+}
+
+impl<'a> System<'a> for UpdateBullets {
+    type SystemData = UpdateBulletsData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        let impacts = Self::find_impacts(&mut data);
 
-            if let Some(health) = data.healths.get_mut(target_entity) {
-                dealt_damage = dealt_damage.min(health.remaining_absolute());
-                Self::damage(bullet, health, &mut data.progression, &mut data.players);
+        let explosive = impacts.iter().any(|impact| {
+            data.bullets
+                .get(impact.bullet)
+                .unwrap()
+                .explosion_radius
+                .is_some()
+        });
+
+        let targets = if explosive {
+            Self::create_targets(&data)
+        } else {
+            IntersectionGrid::new()
+        };
+
+        for impact in impacts {
+            if data.hits.is_dead(impact.bullet) {
+                continue;
             }
 
-            if let Some(health) = data.healths.get_mut(bullet_entity) {
-                health.damage(dealt_damage * 1.01);
+            let bullet = *data.bullets.get(impact.bullet).unwrap();
+
+            match (bullet.explosion_radius, impact.target) {
+                (Some(radius), _) => Self::explode(&mut data, &targets, &impact, radius),
+                (None, Some(target)) => {
+                    if !data.hits.is_dead(target) {
+                        Self::hit(&mut data, impact.bullet, target);
+                    }
+                }
+                (None, None) => {}
             }
         }
     }
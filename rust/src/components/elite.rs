@@ -0,0 +1,266 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use specs::{Component, LazyUpdate, ReadExpect, ReadStorage, System, WriteExpect};
+
+use crate::components::bounds::Bounds;
+use crate::components::health::Health;
+use crate::components::sprite::sprite::Sprite;
+use crate::components::sprite::FrameSprites;
+use crate::entities::demon;
+use crate::game::Timestamp;
+use crate::graphics::texture;
+use crate::map::Map;
+use crate::progression::Progression;
+use crate::util::random::Random;
+use crate::util::vector::Vec2;
+use crate::{vec2, Mat3};
+
+const ELITE_FIRST_ROUND: usize = 2;
+const MAX_ELITE_CHANCE: f64 = 0.3;
+const MAX_AFFIXES: usize = 3;
+
+const FAST_SPEED_FACTOR: f64 = 1.5;
+const ARMORED_DAMAGE_FACTOR: f64 = 0.6;
+/// Fraction of the damage dealt to players that a vampiric enemy heals.
+const VAMPIRIC_DRAIN: f64 = 0.5;
+
+const SPLIT_COUNT: usize = 2;
+const SPLIT_HEALTH_FRACTION: f64 = 0.35;
+const SPLIT_OFFSET: f64 = 0.2;
+
+const HIT_CREDITS_PER_AFFIX: usize = 5;
+const KILL_CREDITS_PER_AFFIX: usize = 50;
+
+/// How long each of several auras is shown before cycling to the next.
+const AURA_DURATION: f64 = 0.5;
+const AURA_SIZE: Vec2 = vec2(0.5, 0.5);
+
+lazy_static! {
+    static ref AURA_TEXTURES: [Mat3; 5] = texture::elite_aura();
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Affix {
+    /// Moves faster.
+    Fast,
+    /// Takes less damage from every hit.
+    Armored,
+    /// Heals back to full after a while without taking damage.
+    Regenerating,
+    /// Splits into smaller demons when killed.
+    Splitting,
+    /// Heals by damaging players.
+    Vampiric,
+}
+
+impl Affix {
+    const ALL: [Affix; 5] = [
+        Affix::Fast,
+        Affix::Armored,
+        Affix::Regenerating,
+        Affix::Splitting,
+        Affix::Vampiric,
+    ];
+
+    fn rarity(self) -> f64 {
+        match self {
+            Affix::Fast => 1.0,
+            Affix::Armored => 1.0,
+            Affix::Regenerating => 1.5,
+            Affix::Splitting => 2.0,
+            Affix::Vampiric => 1.5,
+        }
+    }
+
+    fn aura(self) -> Mat3 {
+        AURA_TEXTURES[self as usize]
+    }
+}
+
+/// Affixes rolled for a single enemy, making it stronger and worth more credits.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Elite {
+    affixes: Vec<Affix>,
+}
+
+impl Elite {
+    /// Occasionally rolls affixes for an enemy spawned in `round`, becoming more likely and
+    /// stacking more affixes in later rounds.
+    pub fn roll(round: usize, random: &mut Random) -> Option<Self> {
+        if round < ELITE_FIRST_ROUND {
+            return None;
+        }
+
+        let rounds = (round - ELITE_FIRST_ROUND) as f64;
+        let chance = (0.05 + rounds * 0.015).min(MAX_ELITE_CHANCE);
+
+        if random.next_f64() >= chance {
+            return None;
+        }
+
+        let extra_affix_chance = (rounds * 0.03).min(0.5);
+        let mut count = 1;
+
+        while count < MAX_AFFIXES && random.next_f64() < extra_affix_chance {
+            count += 1;
+        }
+
+        let mut candidates = Affix::ALL.to_vec();
+        let mut affixes = Vec::with_capacity(count);
+
+        for _ in 0..count {
+            let index =
+                random.weighted_index(|| candidates.iter().map(|affix| 1.0 / affix.rarity()));
+            affixes.push(candidates.remove(index));
+        }
+
+        Some(Self { affixes })
+    }
+
+    pub fn has(&self, affix: Affix) -> bool {
+        self.affixes.contains(&affix)
+    }
+
+    pub fn speed_factor(&self) -> f64 {
+        if self.has(Affix::Fast) {
+            FAST_SPEED_FACTOR
+        } else {
+            1.0
+        }
+    }
+
+    pub fn damage_factor(&self) -> f64 {
+        if self.has(Affix::Armored) {
+            ARMORED_DAMAGE_FACTOR
+        } else {
+            1.0
+        }
+    }
+
+    /// Health healed back from dealing `damage` to a player.
+    pub fn drain(&self, damage: f64) -> f64 {
+        if self.has(Affix::Vampiric) {
+            damage * VAMPIRIC_DRAIN
+        } else {
+            0.0
+        }
+    }
+
+    /// Credits paid on top of the usual amount for hitting or killing this enemy.
+    pub fn bonus_credits(&self, killed: bool) -> usize {
+        let per_affix = if killed {
+            KILL_CREDITS_PER_AFFIX
+        } else {
+            HIT_CREDITS_PER_AFFIX
+        };
+
+        per_affix * self.affixes.len()
+    }
+
+    fn current_aura(&self, timestamp: f64) -> Mat3 {
+        let index = (timestamp / AURA_DURATION) as usize % self.affixes.len();
+        self.affixes[index].aura()
+    }
+}
+
+impl Component for Elite {
+    type Storage = HashMapStorage<Self>;
+}
+
+pub struct SplitElites;
+
+#[derive(SystemData)]
+pub struct SplitElitesData<'a> {
+    lazy_update: ReadExpect<'a, LazyUpdate>,
+    map: ReadExpect<'a, Map>,
+    progression: WriteExpect<'a, Progression>,
+    bounds: ReadStorage<'a, Bounds>,
+    healths: ReadStorage<'a, Health>,
+    elites: ReadStorage<'a, Elite>,
+}
+
+impl<'a> System<'a> for SplitElites {
+    type SystemData = SplitElitesData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        for (bounds, health, elite) in (&data.bounds, &data.healths, &data.elites).join() {
+            if !health.is_dead() || !elite.has(Affix::Splitting) {
+                continue;
+            }
+
+            let center = bounds.0.center();
+            let health = health.maximum() * SPLIT_HEALTH_FRACTION;
+            let speed = data.progression.enemy_speed();
+
+            let spawns = (0..SPLIT_COUNT)
+                .map(|index| {
+                    let side = index as f64 * 2.0 - (SPLIT_COUNT - 1) as f64;
+                    center + Vec2::right() * side * SPLIT_OFFSET
+                })
+                .map(|spawn| {
+                    if data.map.at(spawn.coord()).is_walkable() {
+                        spawn
+                    } else {
+                        center
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            data.progression.report_enemies_added(spawns.len());
+
+            data.lazy_update.exec_mut(move |world| {
+                for spawn in spawns {
+                    demon::create_split(world, spawn, health, speed);
+                }
+            });
+        }
+    }
+}
+
+pub struct GenerateEliteAuras;
+
+#[derive(SystemData)]
+pub struct GenerateEliteAurasData<'a> {
+    frame_sprites: WriteExpect<'a, FrameSprites>,
+    timestamp: ReadExpect<'a, Timestamp>,
+    bounds: ReadStorage<'a, Bounds>,
+    elites: ReadStorage<'a, Elite>,
+}
+
+impl<'a> System<'a> for GenerateEliteAuras {
+    type SystemData = GenerateEliteAurasData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        for (bounds, elite) in (&data.bounds, &data.elites).join() {
+            let texture = elite.current_aura(data.timestamp.0);
+            let sprite = Sprite::new_sized(texture, AURA_SIZE);
+            data.frame_sprites.draw_sprite(bounds.0.center(), sprite);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolled_affixes_are_distinct() {
+        let mut random = Random::new(42);
+
+        assert!((0..1000).all(|_| Elite::roll(0, &mut random).is_none()));
+
+        for _ in 0..1000 {
+            if let Some(elite) = Elite::roll(60, &mut random) {
+                let affixes = &elite.affixes;
+                assert!(!affixes.is_empty() && affixes.len() <= MAX_AFFIXES);
+
+                for (i, affix) in affixes.iter().enumerate() {
+                    assert!(!affixes[i + 1..].contains(affix));
+                }
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::components::boss::Boss;
 use crate::components::bounds::Bounds;
 use crate::components::health::Health;
 use crate::components::player::Player;
@@ -21,6 +22,7 @@ pub struct KillLostEnemiesData<'a> {
     bounds: ReadStorage<'a, Bounds>,
     healths: WriteStorage<'a, Health>,
     enemies: ReadStorage<'a, Enemy>,
+    bosses: ReadStorage<'a, Boss>,
     players: ReadStorage<'a, Player>,
 }
 
@@ -37,8 +39,15 @@ impl<'a> System<'a> for KillLostEnemies {
             return;
         }
 
-        let iter = (&data.bounds, &mut data.healths, &data.enemies).join();
-        'enemyLoop: for (bounds, health, _) in iter {
+        // Bosses can't be lost, since respawning them elsewhere would reset the fight.
+        let iter = (
+            &data.bounds,
+            &mut data.healths,
+            &data.enemies,
+            !&data.bosses,
+        )
+            .join();
+        'enemyLoop: for (bounds, health, _, _) in iter {
             for player in &player_positions {
                 let distance = Vec2::distance_squared(*player, bounds.0.center());
                 if distance < SPAWNER_SEARCH_SIZE.powi(2) {
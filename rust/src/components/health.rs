@@ -1,6 +1,9 @@
+use serde::{Deserialize, Serialize};
 use specs::{Component, Entities, Join, ReadStorage, System, VecStorage};
 
-#[derive(Copy, Clone)]
+use crate::components::player::Player;
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
 pub struct Health {
     remaining: f64,
     max: f64,
@@ -24,6 +27,12 @@ impl Health {
         self.damage(-amount);
     }
 
+    /// Raises the maximum by `amount`, healing by the same amount.
+    pub fn raise_maximum(&mut self, amount: f64) {
+        self.max += amount;
+        self.remaining += amount;
+    }
+
     pub fn kill(&mut self) {
         self.remaining = 0.0;
     }
@@ -52,11 +61,16 @@ impl Component for Health {
 pub struct DeleteDeadEntities;
 
 impl<'a> System<'a> for DeleteDeadEntities {
-    type SystemData = (Entities<'a>, ReadStorage<'a, Health>);
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Health>,
+        ReadStorage<'a, Player>,
+    );
 
-    fn run(&mut self, (entities, healths): Self::SystemData) {
-        for (entity, health) in (&entities, &healths).join() {
-            if health.is_dead() {
+    fn run(&mut self, (entities, healths, players): Self::SystemData) {
+        for (entity, health, player) in (&entities, &healths, players.maybe()).join() {
+            // Downed players stay around until they're revived or bleed out.
+            if health.is_dead() && !player.map_or(false, Player::is_down) {
                 let _ = entities.delete(entity);
             }
         }
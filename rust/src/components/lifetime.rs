@@ -0,0 +1,45 @@
+use specs::prelude::*;
+use specs::{Component, Entities, ReadExpect, System, WriteStorage};
+
+use crate::game::Delta;
+
+/// Time left before a short-lived entity, such as an explosion or a beam, is deleted.
+pub struct Lifetime {
+    remaining_time: f64,
+}
+
+impl Lifetime {
+    pub fn new(duration: f64) -> Self {
+        Self {
+            remaining_time: duration,
+        }
+    }
+
+    pub fn remaining_time(&self) -> f64 {
+        self.remaining_time
+    }
+}
+
+impl Component for Lifetime {
+    type Storage = HashMapStorage<Self>;
+}
+
+pub struct ExpireLifetimes;
+
+impl<'a> System<'a> for ExpireLifetimes {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Delta>,
+        WriteStorage<'a, Lifetime>,
+    );
+
+    fn run(&mut self, (entities, delta, mut lifetimes): Self::SystemData) {
+        for (entity, lifetime) in (&entities, &mut lifetimes).join() {
+            lifetime.remaining_time -= delta.0;
+
+            if lifetime.remaining_time <= 0.0 {
+                let _ = entities.delete(entity);
+            }
+        }
+    }
+}
@@ -3,8 +3,11 @@ use specs::prelude::*;
 use specs::{ReadStorage, System};
 
 use crate::components::bounds::Bounds;
+use crate::components::bullet::Faction;
+use crate::components::elite::Elite;
 use crate::components::health::Health;
 use crate::components::player::Player;
+use crate::components::status_effects::{StatusEffect, StatusEffects};
 use crate::game::Delta;
 
 const REACH: f64 = 0.2;
@@ -14,6 +17,7 @@ pub struct MeleeAttacker {
     cooldown: f64,
     delay: f64,
     remaining_cooldown: f64,
+    effect: Option<StatusEffect>,
 }
 
 impl MeleeAttacker {
@@ -23,6 +27,15 @@ impl MeleeAttacker {
             cooldown,
             delay,
             remaining_cooldown: cooldown,
+            effect: None,
+        }
+    }
+
+    /// Makes every hit inflict `effect` on the player.
+    pub fn with_effect(self, effect: StatusEffect) -> Self {
+        Self {
+            effect: Some(effect),
+            ..self
         }
     }
 }
@@ -35,28 +48,62 @@ pub struct AttackPlayers;
 
 #[derive(SystemData)]
 pub struct MonsterAttackingData<'a> {
+    entities: Entities<'a>,
     delta: ReadExpect<'a, Delta>,
     bounds: ReadStorage<'a, Bounds>,
     healths: WriteStorage<'a, Health>,
     attackers: WriteStorage<'a, MeleeAttacker>,
     players: ReadStorage<'a, Player>,
+    elites: ReadStorage<'a, Elite>,
+    status_effects: WriteStorage<'a, StatusEffects>,
 }
 
 impl<'a> System<'a> for AttackPlayers {
     type SystemData = MonsterAttackingData<'a>;
 
     fn run(&mut self, mut data: Self::SystemData) {
-        for (bounds, attacker) in (&data.bounds, &mut data.attackers).join() {
+        let iter = (&data.entities, &data.bounds, &mut data.attackers).join();
+        for (entity, bounds, attacker) in iter {
+            let stunned = data
+                .status_effects
+                .get(entity)
+                .map_or(false, StatusEffects::is_stunned);
+
+            if stunned {
+                continue;
+            }
+
             if attacker.remaining_cooldown > 0.0 {
                 attacker.remaining_cooldown -= data.delta.0;
                 continue;
             }
 
-            let players_iter = (&data.bounds, &mut data.healths, &data.players).join();
-            for (player_bounds, player_health, _) in players_iter {
-                if bounds.0.euclidean_distance(player_bounds.0) <= REACH {
+            let mut dealt_damage = 0.0;
+
+            let players_iter = (
+                &data.entities,
+                &data.bounds,
+                &mut data.healths,
+                &data.players,
+            )
+                .join();
+            for (player_entity, player_bounds, player_health, player) in players_iter {
+                let in_reach = bounds.0.euclidean_distance(player_bounds.0) <= REACH;
+
+                if in_reach && !player.is_invulnerable() {
+                    dealt_damage += attacker.damage.min(player_health.remaining_absolute());
                     player_health.damage(attacker.damage);
 
+                    if let Some(effect) = attacker.effect {
+                        StatusEffects::inflict(
+                            &mut data.status_effects,
+                            player_entity,
+                            effect,
+                            entity,
+                            Faction::Enemies,
+                        );
+                    }
+
                     play_sound(sound::player_hit());
                     if player_health.is_dead() {
                         play_sound(sound::death());
@@ -71,6 +118,17 @@ impl<'a> System<'a> for AttackPlayers {
                     }
                 }
             }
+
+            let drained = data
+                .elites
+                .get(entity)
+                .map_or(0.0, |elite| elite.drain(dealt_damage));
+
+            if drained > 0.0 {
+                if let Some(health) = data.healths.get_mut(entity) {
+                    health.heal(drained);
+                }
+            }
         }
     }
 }
@@ -1,11 +1,17 @@
+pub mod boss;
 pub mod bounds;
 pub mod bullet;
+pub mod elite;
 pub mod enemy;
 pub mod health;
+pub mod lifetime;
 pub mod melee_attacker;
 pub mod physics;
+pub mod pickup;
 pub mod player;
 pub mod player_seeker;
+pub mod ranged_attacker;
 pub mod regen;
 pub mod spawning_demon;
 pub mod sprite;
+pub mod status_effects;
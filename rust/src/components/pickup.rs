@@ -0,0 +1,229 @@
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use specs::{Component, Entities, LazyUpdate, ReadExpect, ReadStorage, System, WriteStorage};
+
+use crate::audio::{play_sound, sound};
+use crate::components::bounds::Bounds;
+use crate::components::enemy::Enemy;
+use crate::components::health::Health;
+use crate::components::lifetime::Lifetime;
+use crate::components::physics::Physics;
+use crate::components::player::{Player, PowerUp};
+use crate::components::sprite::sprite::Sprite;
+use crate::components::sprite::FrameSprites;
+use crate::entities::pickup;
+use crate::game::Timestamp;
+use crate::graphics::texture;
+use crate::progression::Progression;
+use crate::util::random::{Random, RandomStreams};
+use crate::{vec2, Mat3};
+
+const MAX_DROP_CHANCE: f64 = 0.25;
+const POWER_UP_FIRST_ROUND: usize = 3;
+
+/// Fraction of a gun's total ammo restored by an ammo pickup.
+const AMMO_FRACTION: f64 = 0.35;
+/// Fraction of a player's maximum health restored by a health pickup.
+const HEALTH_FRACTION: f64 = 0.3;
+const POWER_UP_DURATION: f64 = 10.0;
+
+/// Remaining time below which a pickup blinks to warn that it's about to despawn.
+const BLINK_TIME: f64 = 3.0;
+const BLINK_INTERVAL: f64 = 0.15;
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Pickup {
+    Ammo,
+    Health,
+    Credits { amount: usize },
+    PowerUp { power_up: PowerUp },
+}
+
+impl Pickup {
+    /// Rolls what an enemy killed in `round` drops, if anything. Health and power-ups become
+    /// more common in later rounds, and credit bags grow.
+    pub fn roll(round: usize, random: &mut Random) -> Option<Self> {
+        let chance = (0.12 + round as f64 * 0.005).min(MAX_DROP_CHANCE);
+
+        if random.next_f64() >= chance {
+            return None;
+        }
+
+        let power_up_weight = if round >= POWER_UP_FIRST_ROUND {
+            1.0
+        } else {
+            0.0
+        };
+
+        let weights = [
+            4.0,
+            (1.5 + round as f64 * 0.15).min(4.0),
+            3.0,
+            power_up_weight,
+        ];
+
+        let pickup = match random.weighted_index(|| weights.iter().copied()) {
+            0 => Pickup::Ammo,
+            1 => Pickup::Health,
+            2 => Pickup::Credits {
+                amount: 50 + round * 10,
+            },
+            _ => Pickup::PowerUp {
+                power_up: *random.element(&[PowerUp::Damage, PowerUp::RapidFire]),
+            },
+        };
+
+        Some(pickup)
+    }
+
+    pub fn texture(&self) -> Mat3 {
+        match self {
+            Pickup::Ammo => texture::ammo_pickup(),
+            Pickup::Health => texture::health_pickup(),
+            Pickup::Credits { .. } => texture::credits_pickup(),
+            Pickup::PowerUp {
+                power_up: PowerUp::Damage,
+            } => texture::damage_power_up(),
+            Pickup::PowerUp {
+                power_up: PowerUp::RapidFire,
+            } => texture::rapid_fire_power_up(),
+        }
+    }
+
+    /// Whether the player would get anything out of this pickup, so that it's left for others
+    /// otherwise.
+    fn is_useful(&self, player: &Player, health: &Health) -> bool {
+        match self {
+            Pickup::Ammo => player.selected_gun().missing_ammo() > 0,
+            Pickup::Health => health.remaining_relative() < 1.0,
+            Pickup::Credits { .. } | Pickup::PowerUp { .. } => true,
+        }
+    }
+
+    fn apply(&self, player: &mut Player, health: &mut Health) {
+        match *self {
+            Pickup::Ammo => {
+                let gun = player.selected_gun_mut();
+                let amount = (gun.spec().ammo() as f64 * AMMO_FRACTION).ceil() as usize;
+                gun.restock(amount);
+            }
+            Pickup::Health => health.heal(health.maximum() * HEALTH_FRACTION),
+            Pickup::Credits { amount } => *player.credits_mut() += amount,
+            Pickup::PowerUp { power_up } => player.grant_power_up(power_up, POWER_UP_DURATION),
+        }
+    }
+}
+
+impl Component for Pickup {
+    type Storage = DenseVecStorage<Self>;
+}
+
+pub struct DropPickups;
+
+#[derive(SystemData)]
+pub struct DropPickupsData<'a> {
+    lazy_update: ReadExpect<'a, LazyUpdate>,
+    progression: ReadExpect<'a, Progression>,
+    random: WriteExpect<'a, RandomStreams>,
+    bounds: ReadStorage<'a, Bounds>,
+    healths: ReadStorage<'a, Health>,
+    enemies: ReadStorage<'a, Enemy>,
+}
+
+impl<'a> System<'a> for DropPickups {
+    type SystemData = DropPickupsData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        for (bounds, health, _) in (&data.bounds, &data.healths, &data.enemies).join() {
+            if !health.is_dead() {
+                continue;
+            }
+
+            let dropped = Pickup::roll(data.progression.round(), data.random.loot());
+
+            if let Some(dropped) = dropped {
+                let position = bounds.0.center();
+                data.lazy_update.exec_mut(move |world| {
+                    pickup::create(world, position, dropped);
+                });
+            }
+        }
+    }
+}
+
+pub struct CollectPickups;
+
+#[derive(SystemData)]
+pub struct CollectPickupsData<'a> {
+    entities: Entities<'a>,
+    physicses: ReadStorage<'a, Physics>,
+    pickups: ReadStorage<'a, Pickup>,
+    players: WriteStorage<'a, Player>,
+    healths: WriteStorage<'a, Health>,
+}
+
+impl<'a> System<'a> for CollectPickups {
+    type SystemData = CollectPickupsData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        let iter = (&data.entities, &data.physicses, &data.pickups).join();
+
+        for (entity, physics, pickup) in iter {
+            for hit in physics.collisions().hit_entities().unwrap() {
+                let player = data.players.get_mut(hit);
+                let health = data.healths.get_mut(hit);
+
+                let (player, health) = match (player, health) {
+                    (Some(player), Some(health)) => (player, health),
+                    _ => continue,
+                };
+
+                if health.is_dead() || !pickup.is_useful(player, health) {
+                    continue;
+                }
+
+                play_sound(sound::pickup());
+                pickup.apply(player, health);
+
+                let _ = data.entities.delete(entity);
+                break;
+            }
+        }
+    }
+}
+
+pub struct GeneratePickupSprites;
+
+#[derive(SystemData)]
+pub struct GeneratePickupSpritesData<'a> {
+    frame_sprites: WriteExpect<'a, FrameSprites>,
+    timestamp: ReadExpect<'a, Timestamp>,
+    bounds: ReadStorage<'a, Bounds>,
+    pickups: ReadStorage<'a, Pickup>,
+    lifetimes: ReadStorage<'a, Lifetime>,
+}
+
+impl<'a> System<'a> for GeneratePickupSprites {
+    type SystemData = GeneratePickupSpritesData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        let timestamp = data.timestamp.0;
+        let iter = (&data.bounds, &data.pickups, &data.lifetimes).join();
+
+        for (bounds, pickup, lifetime) in iter {
+            let remaining_time = lifetime.remaining_time();
+            let blink_off = (remaining_time / BLINK_INTERVAL) as usize % 2 == 1;
+
+            if remaining_time < BLINK_TIME && blink_off {
+                continue;
+            }
+
+            let y = 0.1 + (timestamp * 3.0).sin() * 0.05;
+            let transform = Mat3::transform(vec2(0.0, y), vec2(0.4, 0.4));
+
+            let sprite = Sprite::new_transformed(pickup.texture(), transform);
+            data.frame_sprites.draw_sprite(bounds.0.center(), sprite);
+        }
+    }
+}
@@ -1,12 +1,69 @@
+use crate::audio::{play_sound, sound};
+use crate::components::bounds::Bounds;
+use crate::components::health::Health;
+use crate::components::status_effects::{StatusEffect, StatusEffects};
 use crate::game::Delta;
 use crate::gun::{Gun, GunSpec};
+use crate::perk::{self, Perk};
 use crate::util::vector::Vec2;
 use crate::Inputs;
-use specs::{Component, HashMapStorage, Join, ReadExpect, System, WriteStorage};
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use specs::{
+    Component, Entities, HashMapStorage, Join, ReadExpect, ReadStorage, System, WriteStorage,
+};
 use std::rc::Rc;
 
 const STARTING_CREDITS: usize = 500;
 
+const DAMAGE_POWER_UP_FACTOR: f64 = 2.0;
+const RAPID_FIRE_POWER_UP_FACTOR: f64 = 0.5;
+
+const DASH_SPEED: f64 = 12.0;
+const DASH_DURATION: f64 = 0.15;
+/// How long a dash protects from enemy attacks, lasting a little longer than the dash itself.
+const DASH_INVULNERABILITY: f64 = 0.3;
+const DASH_COOLDOWN: f64 = 1.5;
+
+/// How long a downed player can wait for a revive before dying.
+const BLEED_OUT_TIME: f64 = 30.0;
+const REVIVE_TIME: f64 = 3.0;
+/// How close another player has to stay for a revive to continue.
+const REVIVE_RANGE: f64 = 1.0;
+const REVIVE_HEALTH_FRACTION: f64 = 0.3;
+
+/// A temporary boost granted by picking up a power-up.
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PowerUp {
+    Damage,
+    RapidFire,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ActivePowerUp {
+    power_up: PowerUp,
+    remaining_time: f64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ActiveDash {
+    direction: Vec2,
+    remaining_time: f64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Downed {
+    bleed_out_time: f64,
+    /// Time left until the player is back up, while someone is reviving them.
+    revive_time: Option<f64>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Player {
     guns: Vec<Gun>,
     max_guns: usize,
@@ -16,6 +73,18 @@ pub struct Player {
     credits: usize,
     mouse_position: Vec2,
     mouse_down: bool,
+    #[serde(default)]
+    power_ups: Vec<ActivePowerUp>,
+    #[serde(default)]
+    perks: Vec<Perk>,
+    #[serde(default)]
+    dash: Option<ActiveDash>,
+    #[serde(default)]
+    dash_cooldown: f64,
+    #[serde(default)]
+    invulnerable_time: f64,
+    #[serde(default)]
+    downed: Option<Downed>,
 }
 
 impl Player {
@@ -31,6 +100,12 @@ impl Player {
             credits: STARTING_CREDITS,
             mouse_position: Vec2::zero(),
             mouse_down: false,
+            power_ups: Vec::new(),
+            perks: Vec::new(),
+            dash: None,
+            dash_cooldown: 0.0,
+            invulnerable_time: 0.0,
+            downed: None,
         }
     }
 
@@ -48,24 +123,140 @@ impl Player {
     }
 
     pub fn try_attack(&mut self) -> AttackResult {
+        let damage_factor = if self.has_power_up(PowerUp::Damage) {
+            DAMAGE_POWER_UP_FACTOR
+        } else {
+            1.0
+        };
+
+        let cooldown_factor = if self.has_power_up(PowerUp::RapidFire) {
+            RAPID_FIRE_POWER_UP_FACTOR
+        } else {
+            1.0
+        };
+
         let gun = &mut self.guns[self.selected_gun];
 
-        if self.attack_cooldown <= 0.0 && *gun.current_ammo_mut() > 0 {
-            self.attack_cooldown = gun.spec().cooldown();
-            *gun.current_ammo_mut() -= 1;
+        if gun.current_ammo() == 0 {
+            gun.start_reload();
+        }
+
+        if self.attack_cooldown <= 0.0 && gun.can_fire() {
+            self.attack_cooldown = gun.spec().cooldown() * cooldown_factor;
+            gun.fire();
 
             AttackResult::Can {
-                damage: gun.spec().damage(),
+                damage: gun.spec().damage() * damage_factor,
                 knockback: gun.spec().knockback(),
                 penetration: gun.spec().penetration(),
                 inaccuracy: gun.spec().inaccuracy(),
                 bullet_count: gun.spec().bullet_count(),
+                explosion_radius: gun.spec().explosion_radius(),
+                hitscan: gun.spec().hitscan(),
+                effect: gun.spec().effect(),
             }
         } else {
             AttackResult::Cant
         }
     }
 
+    /// Grants `power_up` for `duration` seconds, refreshing it if it's already active.
+    pub fn grant_power_up(&mut self, power_up: PowerUp, duration: f64) {
+        self.power_ups.retain(|active| active.power_up != power_up);
+        self.power_ups.push(ActivePowerUp {
+            power_up,
+            remaining_time: duration,
+        });
+    }
+
+    pub fn has_power_up(&self, power_up: PowerUp) -> bool {
+        self.power_ups
+            .iter()
+            .any(|active| active.power_up == power_up)
+    }
+
+    /// Starts dashing in `direction`, returning whether the dash was ready.
+    pub fn try_dash(&mut self, direction: Vec2) -> bool {
+        if self.dash_cooldown > 0.0 || direction == Vec2::zero() {
+            return false;
+        }
+
+        self.dash = Some(ActiveDash {
+            direction: direction.normalize(),
+            remaining_time: DASH_DURATION,
+        });
+        self.dash_cooldown = DASH_COOLDOWN;
+        self.invulnerable_time = DASH_INVULNERABILITY;
+
+        true
+    }
+
+    /// The velocity the player is locked to while dashing.
+    pub fn dash_velocity(&self) -> Option<Vec2> {
+        self.dash.as_ref().map(|dash| dash.direction * DASH_SPEED)
+    }
+
+    pub fn is_dashing(&self) -> bool {
+        self.dash.is_some()
+    }
+
+    /// Whether enemy attacks currently miss the player, such as while dashing or downed.
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable_time > 0.0 || self.is_down()
+    }
+
+    /// Whether the player ran out of health and is waiting to be revived.
+    pub fn is_down(&self) -> bool {
+        self.downed.is_some()
+    }
+
+    /// Starts reviving a downed player, returning whether a revive wasn't already in progress.
+    pub fn start_revive(&mut self) -> bool {
+        match &mut self.downed {
+            Some(downed) if downed.revive_time.is_none() => {
+                downed.revive_time = Some(REVIVE_TIME);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_being_revived(&self) -> bool {
+        self.downed
+            .as_ref()
+            .map_or(false, |downed| downed.revive_time.is_some())
+    }
+
+    /// Remaining bleed-out time relative to its full duration, or `None` if not downed.
+    pub fn bleed_out(&self) -> Option<f64> {
+        self.downed
+            .as_ref()
+            .map(|downed| downed.bleed_out_time / BLEED_OUT_TIME)
+    }
+
+    /// Remaining dash cooldown relative to the full cooldown, reaching 0 once the dash is ready.
+    pub fn dash_cooldown(&self) -> f64 {
+        self.dash_cooldown / DASH_COOLDOWN
+    }
+
+    /// Grants `perk` permanently. Perks living on other components, such as extra maximum
+    /// health, are applied by the caller.
+    pub fn add_perk(&mut self, perk: Perk) {
+        if perk == Perk::ExtraSlot {
+            self.max_guns += 1;
+        }
+
+        self.perks.push(perk);
+    }
+
+    pub fn has_perk(&self, perk: Perk) -> bool {
+        self.perks.contains(&perk)
+    }
+
+    pub fn perks(&self) -> &[Perk] {
+        &self.perks
+    }
+
     pub fn credits(&self) -> usize {
         self.credits
     }
@@ -78,7 +269,17 @@ impl Player {
         &self.guns[self.selected_gun]
     }
 
+    pub fn selected_gun_mut(&mut self) -> &mut Gun {
+        &mut self.guns[self.selected_gun]
+    }
+
+    pub fn reload(&mut self) {
+        self.guns[self.selected_gun].start_reload();
+    }
+
     pub fn equip_gun(&mut self, gun: Gun) {
+        self.guns[self.selected_gun].cancel_reload();
+
         if self.guns.len() < self.max_guns {
             self.selected_gun = self.guns.len();
             self.last_selected_gun = self.selected_gun;
@@ -89,7 +290,8 @@ impl Player {
     }
 
     pub fn select_gun(&mut self, slot: usize) {
-        if slot < self.guns.len() {
+        if slot < self.guns.len() && slot != self.selected_gun {
+            self.guns[self.selected_gun].cancel_reload();
             self.last_selected_gun = self.selected_gun;
             self.selected_gun = slot;
         }
@@ -116,6 +318,9 @@ pub enum AttackResult {
         penetration: f64,
         inaccuracy: f64,
         bullet_count: usize,
+        explosion_radius: f64,
+        hitscan: bool,
+        effect: Option<StatusEffect>,
     },
 }
 
@@ -132,3 +337,147 @@ impl<'a> System<'a> for ReduceAttackCooldowns {
         }
     }
 }
+
+pub struct ProgressReloads;
+
+impl<'a> System<'a> for ProgressReloads {
+    type SystemData = (ReadExpect<'a, Delta>, WriteStorage<'a, Player>);
+
+    fn run(&mut self, (delta, mut players): Self::SystemData) {
+        for player in (&mut players).join() {
+            let delta = if player.has_perk(Perk::QuickReload) {
+                delta.0 * perk::QUICK_RELOAD_FACTOR
+            } else {
+                delta.0
+            };
+
+            player.guns[player.selected_gun].update_reload(delta);
+        }
+    }
+}
+
+pub struct UpdateDashes;
+
+impl<'a> System<'a> for UpdateDashes {
+    type SystemData = (ReadExpect<'a, Delta>, WriteStorage<'a, Player>);
+
+    fn run(&mut self, (delta, mut players): Self::SystemData) {
+        for player in (&mut players).join() {
+            player.dash_cooldown = (player.dash_cooldown - delta.0).max(0.0);
+            player.invulnerable_time = (player.invulnerable_time - delta.0).max(0.0);
+
+            if let Some(dash) = &mut player.dash {
+                dash.remaining_time -= delta.0;
+
+                if dash.remaining_time <= 0.0 {
+                    player.dash = None;
+                }
+            }
+        }
+    }
+}
+
+/// Downs players who ran out of health, then revives them or lets them bleed out. Once every
+/// player is down there's nobody left to revive them, so they all die.
+pub struct UpdateDownedPlayers;
+
+#[derive(SystemData)]
+pub struct UpdateDownedPlayersData<'a> {
+    entities: Entities<'a>,
+    delta: ReadExpect<'a, Delta>,
+    bounds: ReadStorage<'a, Bounds>,
+    healths: WriteStorage<'a, Health>,
+    players: WriteStorage<'a, Player>,
+    status_effects: WriteStorage<'a, StatusEffects>,
+}
+
+impl<'a> System<'a> for UpdateDownedPlayers {
+    type SystemData = UpdateDownedPlayersData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        for (entity, health, player) in (&data.entities, &data.healths, &mut data.players).join() {
+            if health.is_dead() && !player.is_down() {
+                log::info!("Player {:?} is down", entity);
+
+                player.dash = None;
+                data.status_effects.remove(entity);
+                player.downed = Some(Downed {
+                    bleed_out_time: BLEED_OUT_TIME,
+                    revive_time: None,
+                });
+            }
+        }
+
+        let standing = (&data.bounds, &data.players)
+            .join()
+            .filter(|(_, player)| !player.is_down())
+            .map(|(bounds, _)| bounds.0.center())
+            .collect::<Vec<_>>();
+
+        let iter = (
+            &data.entities,
+            &data.bounds,
+            &mut data.healths,
+            &mut data.players,
+        )
+            .join();
+
+        for (entity, bounds, health, player) in iter {
+            let downed = match &mut player.downed {
+                Some(downed) => downed,
+                None => continue,
+            };
+
+            if standing.is_empty() {
+                let _ = data.entities.delete(entity);
+                continue;
+            }
+
+            let position = bounds.0.center();
+            let reviver_nearby = standing
+                .iter()
+                .any(|other| Vec2::distance(position, *other) <= REVIVE_RANGE);
+
+            match &mut downed.revive_time {
+                Some(revive_time) if reviver_nearby => {
+                    *revive_time -= data.delta.0;
+
+                    if *revive_time <= 0.0 {
+                        log::info!("Player {:?} was revived", entity);
+                        play_sound(sound::revive());
+
+                        player.downed = None;
+                        health.heal(health.maximum() * REVIVE_HEALTH_FRACTION);
+                    }
+                }
+                _ => {
+                    downed.revive_time = None;
+                    downed.bleed_out_time -= data.delta.0;
+
+                    if downed.bleed_out_time <= 0.0 {
+                        log::info!("Player {:?} bled out", entity);
+                        let _ = data.entities.delete(entity);
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub struct ExpirePowerUps;
+
+impl<'a> System<'a> for ExpirePowerUps {
+    type SystemData = (ReadExpect<'a, Delta>, WriteStorage<'a, Player>);
+
+    fn run(&mut self, (delta, mut players): Self::SystemData) {
+        for player in (&mut players).join() {
+            for active in &mut player.power_ups {
+                active.remaining_time -= delta.0;
+            }
+
+            player
+                .power_ups
+                .retain(|active| active.remaining_time > 0.0);
+        }
+    }
+}
@@ -1,7 +1,11 @@
+use crate::components::boss::Boss;
 use crate::components::bounds::Bounds;
 use crate::components::physics::Physics;
 use crate::components::player::Player;
+use crate::components::ranged_attacker::RangedAttacker;
 use crate::components::sprite::character_animation::Facing;
+use crate::components::status_effects::StatusEffects;
+use crate::flow_field::FlowField;
 use crate::game::Timestamp;
 use float_ord::FloatOrd;
 use specs::prelude::*;
@@ -10,31 +14,49 @@ use specs::{Join, System, WriteStorage};
 
 use crate::map::Map;
 use crate::util::coord::Coord;
-use crate::util::random::Random;
+use crate::util::random::{Random, RandomStreams};
 use crate::util::rect::Rect;
 use crate::util::vector::Vec2;
+use crate::vec2;
 
 const TARGET_REACHED_DISTANCE: f64 = 0.2;
+const DIRECT_STEERING_DISTANCE: f64 = 12.0;
+const RETREAT_FRACTION: f64 = 0.6;
 
 pub struct PlayerSeeker {
     target: Option<Vec2>,
     target_invalid_timestamp: f64,
     speed: f64,
     player_targeting_offset: Vec2,
+    preferred_distance: Option<f64>,
 }
 
 impl PlayerSeeker {
-    pub fn new(speed: f64) -> Self {
-        let player_targeting_offset = (Random::global().next_vec2() - 0.5) * 0.15;
+    pub fn new(speed: f64, random: &mut Random) -> Self {
+        let player_targeting_offset = (random.next_vec2() - 0.5) * 0.15;
 
         Self {
             speed,
             player_targeting_offset,
             target: None,
             target_invalid_timestamp: 0.0,
+            preferred_distance: None,
         }
     }
 
+    /// Makes the seeker stop approaching players it can see once this close, backing off when
+    /// they get much closer.
+    pub fn keeping_distance(self, distance: f64) -> Self {
+        Self {
+            preferred_distance: Some(distance),
+            ..self
+        }
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
     fn invalidate_target(&mut self) {
         self.target = None;
         self.target_invalid_timestamp = 0.0;
@@ -50,6 +72,8 @@ pub struct SeekPlayers;
 impl SeekPlayers {
     fn seek_direction(
         map: &Map,
+        flow_field: &FlowField,
+        random: &mut Random,
         timestamp: f64,
         seeker: &mut PlayerSeeker,
         bounds: &mut Rect,
@@ -70,7 +94,23 @@ impl SeekPlayers {
         let center = bounds.center();
         let coord = center.coord();
 
-        if center.distance(closest_target) < 0.75 {
+        if let Some(preferred_distance) = seeker.preferred_distance {
+            let distance = center.distance(closest_target);
+
+            if distance <= preferred_distance && map.line_of_sight(center, closest_target) {
+                seeker.invalidate_target();
+
+                return if distance < preferred_distance * RETREAT_FRACTION {
+                    (center - closest_target).normalize_or_zero()
+                } else {
+                    Vec2::zero()
+                };
+            }
+        }
+
+        let close = center.distance(closest_target) < 0.75;
+
+        if close || Self::can_steer_directly(map, *bounds, closest_target) {
             seeker.invalidate_target();
             (closest_target - center).normalize_or_zero()
         } else {
@@ -81,8 +121,8 @@ impl SeekPlayers {
             }
 
             if seeker.target_invalid_timestamp <= timestamp {
-                seeker.target = Self::update_target(map, coord, player_bounds);
-                seeker.target_invalid_timestamp = timestamp + Self::repath_delay();
+                seeker.target = Self::update_target(map, flow_field, random, coord);
+                seeker.target_invalid_timestamp = timestamp + Self::repath_delay(random);
             }
 
             if let Some(target) = seeker.target {
@@ -93,37 +133,54 @@ impl SeekPlayers {
         }
     }
 
-    fn repath_delay() -> f64 {
-        Random::global().next_f64_in(0.25..0.5)
-    }
-
-    fn update_target(map: &Map, coord: Coord, player_bounds: &[Rect]) -> Option<Vec2> {
-        let offset = (Random::global().next_vec2() - 0.5) * 0.4;
+    /// Whether the seeker can walk straight at the target without its sides catching on a wall.
+    fn can_steer_directly(map: &Map, bounds: Rect, target: Vec2) -> bool {
+        let center = bounds.center();
+        let offset = target - center;
 
-        if let Some(next_step) = Self::next_step(map, coord, player_bounds) {
-            Some(next_step.center() + offset)
-        } else {
-            None
+        if offset.length() > DIRECT_STEERING_DISTANCE {
+            return false;
         }
+
+        let side = vec2(-offset.y(), offset.x()).normalize_or_zero() * bounds.size.length() / 2.0;
+
+        [Vec2::zero(), side, -side]
+            .into_iter()
+            .all(|side| map.line_of_sight(center + side, target + side))
     }
 
-    fn next_step(map: &Map, coord: Coord, player_bounds: &[Rect]) -> Option<Coord> {
-        let targets = || player_bounds.iter().map(|bounds| bounds.center().coord());
+    fn repath_delay(random: &mut Random) -> f64 {
+        random.next_f64_in(0.25..0.5)
+    }
 
-        let path = map.pathfind(coord, targets, 100.0);
-        path.and_then(|(steps, _)| steps.get(1).or(steps.get(0)).copied())
+    fn update_target(
+        map: &Map,
+        flow_field: &FlowField,
+        random: &mut Random,
+        coord: Coord,
+    ) -> Option<Vec2> {
+        let offset = (random.next_vec2() - 0.5) * 0.4;
+
+        flow_field
+            .next_step(map, coord)
+            .map(|next_step| next_step.center() + offset)
     }
 }
 
 #[derive(SystemData)]
 pub struct SeekPlayersData<'a> {
     map: ReadExpect<'a, Map>,
+    flow_field: ReadExpect<'a, FlowField>,
+    random: WriteExpect<'a, RandomStreams>,
     timestamp: ReadExpect<'a, Timestamp>,
     players: ReadStorage<'a, Player>,
     bounds: WriteStorage<'a, Bounds>,
     physicses: WriteStorage<'a, Physics>,
     facings: WriteStorage<'a, Facing>,
     seekers: WriteStorage<'a, PlayerSeeker>,
+    ranged_attackers: ReadStorage<'a, RangedAttacker>,
+    bosses: ReadStorage<'a, Boss>,
+    status_effects: ReadStorage<'a, StatusEffects>,
 }
 
 impl<'a> System<'a> for SeekPlayers {
@@ -131,8 +188,10 @@ impl<'a> System<'a> for SeekPlayers {
 
     fn run(&mut self, mut data: Self::SystemData) {
         let mut player_bounds = Vec::new();
-        for (bounds, _) in (&data.bounds, &data.players).join() {
-            player_bounds.push(bounds.0);
+        for (bounds, player) in (&data.bounds, &data.players).join() {
+            if !player.is_down() {
+                player_bounds.push(bounds.0);
+            }
         }
 
         let iter = (
@@ -140,19 +199,31 @@ impl<'a> System<'a> for SeekPlayers {
             &mut data.physicses,
             (&mut data.facings).maybe(),
             &mut data.seekers,
+            data.ranged_attackers.maybe(),
+            data.bosses.maybe(),
+            data.status_effects.maybe(),
         )
             .join();
 
-        for (bounds, physics, facing, seeker) in iter {
+        for (bounds, physics, facing, seeker, ranged_attacker, boss, status_effects) in iter {
+            if ranged_attacker.map_or(false, RangedAttacker::is_charging)
+                || boss.map_or(false, Boss::is_attacking)
+            {
+                continue;
+            }
+
             let direction = Self::seek_direction(
                 &data.map,
+                &data.flow_field,
+                data.random.ai(),
                 data.timestamp.0,
                 seeker,
                 &mut bounds.0,
                 &player_bounds,
             );
 
-            physics.accelerate_to(direction * seeker.speed);
+            let speed_factor = status_effects.map_or(1.0, StatusEffects::speed_factor);
+            physics.accelerate_to(direction * seeker.speed * speed_factor);
 
             if let Some(facing) = facing {
                 facing.0 = direction;
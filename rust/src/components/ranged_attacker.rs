@@ -0,0 +1,123 @@
+use float_ord::FloatOrd;
+use specs::prelude::*;
+use specs::{Component, Entities, LazyUpdate, ReadExpect, ReadStorage, System, WriteStorage};
+
+use crate::audio::{play_sound, sound};
+use crate::components::bounds::Bounds;
+use crate::components::player::Player;
+use crate::entities::{bullet, demon};
+use crate::game::Delta;
+use crate::map::Map;
+use crate::util::vector::Vec2;
+
+pub struct RangedAttacker {
+    damage: f64,
+    cooldown: f64,
+    windup: f64,
+    range: f64,
+    remaining_cooldown: f64,
+    remaining_windup: Option<f64>,
+}
+
+impl RangedAttacker {
+    pub fn new(damage: f64, cooldown: f64, windup: f64, range: f64) -> Self {
+        Self {
+            damage,
+            cooldown,
+            windup,
+            range,
+            remaining_cooldown: cooldown,
+            remaining_windup: None,
+        }
+    }
+
+    /// Whether a shot is being telegraphed, during which the attacker stands still.
+    pub fn is_charging(&self) -> bool {
+        self.remaining_windup.is_some()
+    }
+}
+
+impl Component for RangedAttacker {
+    type Storage = HashMapStorage<Self>;
+}
+
+pub struct ShootAtPlayers;
+
+impl ShootAtPlayers {
+    /// The closest player within range that can be seen from `position`.
+    fn find_target(map: &Map, position: Vec2, range: f64, players: &[Vec2]) -> Option<Vec2> {
+        players
+            .iter()
+            .copied()
+            .filter(|player| player.distance(position) <= range)
+            .filter(|player| map.line_of_sight(position, *player))
+            .min_by_key(|player| FloatOrd(player.distance(position)))
+    }
+}
+
+#[derive(SystemData)]
+pub struct ShootAtPlayersData<'a> {
+    entities: Entities<'a>,
+    lazy_update: ReadExpect<'a, LazyUpdate>,
+    map: ReadExpect<'a, Map>,
+    delta: ReadExpect<'a, Delta>,
+    bounds: ReadStorage<'a, Bounds>,
+    players: ReadStorage<'a, Player>,
+    attackers: WriteStorage<'a, RangedAttacker>,
+}
+
+impl<'a> System<'a> for ShootAtPlayers {
+    type SystemData = ShootAtPlayersData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        let player_positions = (&data.bounds, &data.players)
+            .join()
+            .filter(|(_, player)| !player.is_down())
+            .map(|(bounds, _)| bounds.0.center())
+            .collect::<Vec<_>>();
+
+        let iter = (&data.entities, &data.bounds, &mut data.attackers).join();
+        for (entity, bounds, attacker) in iter {
+            let position = bounds.0.center();
+            let target = Self::find_target(&data.map, position, attacker.range, &player_positions);
+
+            match attacker.remaining_windup.as_mut() {
+                Some(remaining_windup) => {
+                    *remaining_windup -= data.delta.0;
+
+                    if *remaining_windup > 0.0 {
+                        continue;
+                    }
+
+                    attacker.remaining_windup = None;
+                    attacker.remaining_cooldown = attacker.cooldown;
+
+                    if let Some(target) = target {
+                        play_sound(sound::shoot_slow());
+
+                        let damage = attacker.damage;
+                        let direction = (target - position).normalize_or_zero();
+
+                        data.lazy_update.exec_mut(move |world| {
+                            bullet::create_hostile(world, entity, damage, position, direction);
+                        });
+                    }
+                }
+                None => {
+                    attacker.remaining_cooldown -= data.delta.0;
+
+                    if attacker.remaining_cooldown > 0.0 || target.is_none() {
+                        continue;
+                    }
+
+                    attacker.remaining_windup = Some(attacker.windup);
+
+                    let windup = attacker.windup;
+                    data.lazy_update.exec_mut(move |world| {
+                        demon::create_charge(world, position, windup);
+                    });
+                }
+            }
+        }
+    }
+}
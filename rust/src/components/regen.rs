@@ -1,11 +1,16 @@
 use crate::components::health::Health;
+use crate::components::player::Player;
 use crate::game::{Delta, Timestamp};
+use crate::perk::{self, Perk};
+use serde::{Deserialize, Serialize};
 use specs::prelude::*;
-use specs::{Component, HashMapStorage, ReadExpect, System, WriteStorage};
+use specs::{Component, HashMapStorage, ReadExpect, ReadStorage, System, WriteStorage};
 
 const HEAL_DELAY: f64 = 5.0;
 const HEAL_DURATION: f64 = 10.0;
 
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct HealthRegen {
     last_health: f64,
     last_damage_time: f64,
@@ -32,24 +37,43 @@ pub struct RegenerateHealthData<'a> {
     delta: ReadExpect<'a, Delta>,
     healths: WriteStorage<'a, Health>,
     regens: WriteStorage<'a, HealthRegen>,
+    players: ReadStorage<'a, Player>,
 }
 
 impl<'a> System<'a> for RegenerateHealth {
     type SystemData = RegenerateHealthData<'a>;
 
     fn run(&mut self, mut data: Self::SystemData) {
-        for (health, regen) in (&mut data.healths, &mut data.regens).join() {
+        let iter = (&mut data.healths, &mut data.regens, data.players.maybe()).join();
+
+        for (health, regen, player) in iter {
+            if player.map_or(false, Player::is_down) {
+                continue;
+            }
+
+            let quick_recovery =
+                player.map_or(false, |player| player.has_perk(Perk::QuickRecovery));
+
+            let (heal_delay, heal_duration) = if quick_recovery {
+                (
+                    HEAL_DELAY * perk::QUICK_RECOVERY_FACTOR,
+                    HEAL_DURATION * perk::QUICK_RECOVERY_FACTOR,
+                )
+            } else {
+                (HEAL_DELAY, HEAL_DURATION)
+            };
+
             if health.remaining_absolute() < regen.last_health {
                 regen.last_damage_time = data.timestamp.0;
             }
 
             regen.last_health = health.remaining_absolute();
 
-            if regen.last_damage_time + HEAL_DELAY > data.timestamp.0 {
+            if regen.last_damage_time + heal_delay > data.timestamp.0 {
                 continue;
             }
 
-            health.heal(health.maximum() * data.delta.0 / HEAL_DURATION);
+            health.heal(health.maximum() * data.delta.0 / heal_duration);
         }
     }
 }
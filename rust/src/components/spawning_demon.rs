@@ -2,22 +2,37 @@ use specs::prelude::*;
 use specs::{Component, Entities, LazyUpdate, ReadExpect, ReadStorage, System};
 
 use crate::components::bounds::Bounds;
+use crate::components::elite::Elite;
 use crate::components::health::Health;
-use crate::entities::demon;
+use crate::entities::demon::{self, DemonKind};
 use crate::game::Delta;
 
 pub struct SpawningDemon {
+    kind: DemonKind,
     remaining_time: f64,
     speed: f64,
 }
 
 impl SpawningDemon {
-    pub fn new(time: f64, speed: f64) -> Self {
+    pub fn new(kind: DemonKind, time: f64, speed: f64) -> Self {
         Self {
+            kind,
             speed,
             remaining_time: time,
         }
     }
+
+    pub fn kind(&self) -> DemonKind {
+        self.kind
+    }
+
+    pub fn remaining_time(&self) -> f64 {
+        self.remaining_time
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
 }
 
 impl Component for SpawningDemon {
@@ -34,6 +49,7 @@ pub struct FinishDemonSpawningData<'a> {
     spawning_demons: WriteStorage<'a, SpawningDemon>,
     bounds: ReadStorage<'a, Bounds>,
     healths: ReadStorage<'a, Health>,
+    elites: ReadStorage<'a, Elite>,
 }
 
 impl<'a> System<'a> for FinishDemonSpawning {
@@ -45,20 +61,23 @@ impl<'a> System<'a> for FinishDemonSpawning {
             &mut data.spawning_demons,
             &data.bounds,
             &data.healths,
+            data.elites.maybe(),
         )
             .join();
 
-        for (entity, spawning_demon, bounds, health) in iter {
+        for (entity, spawning_demon, bounds, health, elite) in iter {
             spawning_demon.remaining_time -= data.delta.0;
 
             if spawning_demon.remaining_time <= 0.0 {
                 let center = bounds.0.center();
                 let health = *health;
                 let speed = spawning_demon.speed;
+                let kind = spawning_demon.kind;
+                let elite = elite.cloned();
 
                 data.lazy_update.exec_mut(move |world| {
                     let _ = world.delete_entity(entity);
-                    demon::create(world, center, health, speed);
+                    demon::create(world, kind, center, health, speed, elite);
                 });
             }
         }
@@ -9,6 +9,7 @@ use crate::components::physics::Physics;
 use crate::components::sprite::animation::{Animation, FrameSet};
 use crate::components::sprite::sprite::Sprite;
 use crate::components::sprite::FrameSprites;
+use crate::components::status_effects::StatusEffects;
 use crate::game::Timestamp;
 use crate::util::vector::Vec2;
 use crate::Mat3;
@@ -19,6 +20,8 @@ pub struct CharacterAnimation {
     frame_sets: &'static [CharacterFrameSet],
     duration: f64,
     offset: Option<f64>,
+    /// How far into its cycle the animation starts, from 0 to 1.
+    phase: f64,
     transform: Mat3,
     preference: usize,
 }
@@ -34,6 +37,7 @@ impl CharacterAnimation {
             frame_sets,
             transform,
             offset: None,
+            phase: 0.0,
             preference: 0,
         }
     }
@@ -42,6 +46,29 @@ impl CharacterAnimation {
         Self::new_transformed(frame_sets, duration, Mat3::scale(size))
     }
 
+    /// Starts the animation `phase` of the way into its cycle, so that characters created
+    /// together don't move in lockstep.
+    pub fn with_phase(self, phase: f64) -> Self {
+        Self { phase, ..self }
+    }
+
+    fn start_offset(&self, timestamp: f64) -> f64 {
+        timestamp - self.phase * self.duration
+    }
+
+    /// Switches to different frame sets, such as for a special move, restarting the animation.
+    pub fn set_frame_sets(&mut self, frame_sets: &'static [CharacterFrameSet], duration: f64) {
+        if std::ptr::eq(self.frame_sets, frame_sets) {
+            return;
+        }
+
+        self.frame_sets = frame_sets;
+        self.duration = duration;
+        self.offset = None;
+        self.phase = 0.0;
+        self.preference = 0;
+    }
+
     pub fn current_frame_set(&self, facing: Vec2) -> usize {
         let facing_bias = self.frame_sets[self.preference].sample;
 
@@ -66,7 +93,7 @@ impl CharacterAnimation {
         Animation::new_transformed_with_offset(
             &frame_set.frame_set,
             self.duration,
-            self.offset.unwrap_or(timestamp),
+            self.offset.unwrap_or_else(|| self.start_offset(timestamp)),
             self.transform,
         )
         .current_frame(timestamp)
@@ -78,7 +105,8 @@ impl CharacterAnimation {
 
         self.preference = frame_set_index;
 
-        let offset = *self.offset.get_or_insert(timestamp);
+        let start_offset = self.start_offset(timestamp);
+        let offset = *self.offset.get_or_insert(start_offset);
 
         let mut animation = Animation::new_transformed_with_offset(
             &frame_set.frame_set,
@@ -153,15 +181,27 @@ pub struct GenerateCharacterAnimationSpritesData<'a> {
     character_animation: WriteStorage<'a, CharacterAnimation>,
     bounds: ReadStorage<'a, Bounds>,
     facings: ReadStorage<'a, Facing>,
+    status_effects: ReadStorage<'a, StatusEffects>,
 }
 
 impl<'a> System<'a> for GenerateCharacterAnimationSprites {
     type SystemData = GenerateCharacterAnimationSpritesData<'a>;
 
     fn run(&mut self, mut data: Self::SystemData) {
-        let iter = (&mut data.character_animation, &data.bounds, &data.facings).join();
-        for (character_animation, bounds, facing) in iter {
-            let sprite = character_animation.update_sprite(facing.0, data.timestamp.0);
+        let iter = (
+            &mut data.character_animation,
+            &data.bounds,
+            &data.facings,
+            data.status_effects.maybe(),
+        )
+            .join();
+        for (character_animation, bounds, facing, status_effects) in iter {
+            let mut sprite = character_animation.update_sprite(facing.0, data.timestamp.0);
+
+            if let Some(status_effects) = status_effects {
+                sprite = sprite.tinted(status_effects.tint());
+            }
+
             data.frame_sprites.draw_sprite(bounds.0.center(), sprite);
         }
     }
@@ -58,10 +58,12 @@ impl<'a> System<'a> for DrawSprites {
                 continue;
             }
 
-            data.buffer.push(EntityRendererSettings {
+            let entity = EntityRendererSettings {
                 transform,
                 uv_transform: sprite.texture(),
-            });
+            };
+
+            data.buffer.push_tinted(entity, sprite.tint());
         }
 
         data.sprites.sprites.clear();
@@ -2,18 +2,23 @@ use crate::components::bounds::Bounds;
 use specs::prelude::*;
 
 use crate::components::sprite::FrameSprites;
-use crate::util::vector::Vec2;
+use crate::util::vector::{Vec2, Vec3};
 use crate::Mat3;
 
 #[derive(Copy, Clone)]
 pub struct Sprite {
     texture: Mat3,
     transform: Mat3,
+    tint: Vec3,
 }
 
 impl Sprite {
     pub fn new_transformed(texture: Mat3, transform: Mat3) -> Self {
-        Self { transform, texture }
+        Self {
+            transform,
+            texture,
+            tint: Vec3::one(),
+        }
     }
 
     pub fn new_sized(texture: Mat3, size: Vec2) -> Self {
@@ -27,6 +32,15 @@ impl Sprite {
     pub fn transform(&self) -> Mat3 {
         self.transform
     }
+
+    pub fn tint(&self) -> Vec3 {
+        self.tint
+    }
+
+    /// The same sprite with its colors multiplied by `tint`.
+    pub fn tinted(self, tint: Vec3) -> Self {
+        Self { tint, ..self }
+    }
 }
 
 impl Component for Sprite {
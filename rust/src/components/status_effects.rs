@@ -0,0 +1,254 @@
+use float_ord::FloatOrd;
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use specs::{Component, Entities, ReadExpect, System, WriteStorage};
+
+use crate::components::bullet::{Faction, HitData};
+use crate::game::Delta;
+use crate::util::vector::{vec3, Vec3};
+
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StatusKind {
+    /// Deals heavy damage over a short time.
+    Burn,
+    /// Deals light damage over a long time.
+    Poison,
+    /// Reduces movement speed.
+    Slow,
+    /// Stops movement and melee attacks entirely.
+    Stun,
+}
+
+impl StatusKind {
+    const ALL: [StatusKind; 4] = [
+        StatusKind::Burn,
+        StatusKind::Poison,
+        StatusKind::Slow,
+        StatusKind::Stun,
+    ];
+
+    /// How many instances of this kind an entity can suffer at once.
+    fn max_stacks(self) -> usize {
+        match self {
+            StatusKind::Burn => 3,
+            StatusKind::Poison => 5,
+            StatusKind::Slow => 1,
+            StatusKind::Stun => 1,
+        }
+    }
+
+    fn deals_damage(self) -> bool {
+        matches!(self, StatusKind::Burn | StatusKind::Poison)
+    }
+
+    fn tint(self) -> Vec3 {
+        match self {
+            StatusKind::Burn => vec3(1.0, 0.55, 0.35),
+            StatusKind::Poison => vec3(0.55, 1.0, 0.45),
+            StatusKind::Slow => vec3(0.5, 0.75, 1.0),
+            StatusKind::Stun => vec3(1.0, 1.0, 0.4),
+        }
+    }
+}
+
+/// An effect as inflicted by a gun modifier or an enemy attack.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub duration: f64,
+    /// Damage per second for burns and poison, or the fraction of speed taken away by a slow.
+    /// Stuns ignore it.
+    #[serde(default)]
+    pub strength: f64,
+}
+
+impl StatusEffect {
+    pub const fn new(kind: StatusKind, duration: f64, strength: f64) -> Self {
+        Self {
+            kind,
+            duration,
+            strength,
+        }
+    }
+
+    /// Whether the effect is well-formed, for validating effects described by data.
+    pub fn is_valid(&self) -> bool {
+        let strength_valid = match self.kind {
+            StatusKind::Slow => self.strength > 0.0 && self.strength < 1.0,
+            _ => self.strength.is_finite() && self.strength >= 0.0,
+        };
+
+        self.duration.is_finite() && self.duration > 0.0 && strength_valid
+    }
+}
+
+struct ActiveEffect {
+    effect: StatusEffect,
+    remaining_time: f64,
+    source: Entity,
+    faction: Faction,
+}
+
+/// Timed effects an entity currently suffers. Removed again once all of them expired.
+#[derive(Default)]
+pub struct StatusEffects {
+    active: Vec<ActiveEffect>,
+}
+
+impl StatusEffects {
+    /// Applies `effect` to `target`, adding the component if it isn't suffering any effects yet.
+    pub fn inflict(
+        storage: &mut WriteStorage<StatusEffects>,
+        target: Entity,
+        effect: StatusEffect,
+        source: Entity,
+        faction: Faction,
+    ) {
+        if let Ok(entry) = storage.entry(target) {
+            entry
+                .or_insert_with(Self::default)
+                .apply(effect, source, faction);
+        }
+    }
+
+    /// Adds another instance of `effect`. Once the limit of stacks for its kind is reached, it
+    /// replaces the instance closest to expiring instead.
+    fn apply(&mut self, effect: StatusEffect, source: Entity, faction: Faction) {
+        let active = ActiveEffect {
+            effect,
+            remaining_time: effect.duration,
+            source,
+            faction,
+        };
+
+        let stacks = self
+            .active
+            .iter()
+            .filter(|active| active.effect.kind == effect.kind)
+            .count();
+
+        if stacks < effect.kind.max_stacks() {
+            self.active.push(active);
+            return;
+        }
+
+        let expiring = self
+            .active
+            .iter_mut()
+            .filter(|active| active.effect.kind == effect.kind)
+            .min_by_key(|active| FloatOrd(active.remaining_time));
+
+        if let Some(expiring) = expiring {
+            *expiring = active;
+        }
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.active.iter().any(|active| active.effect.kind == kind)
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.has(StatusKind::Stun)
+    }
+
+    /// Factor to scale movement speed by, going by the strongest slow. Stuns stop movement.
+    pub fn speed_factor(&self) -> f64 {
+        if self.is_stunned() {
+            return 0.0;
+        }
+
+        let slow = self
+            .active
+            .iter()
+            .filter(|active| active.effect.kind == StatusKind::Slow)
+            .map(|active| active.effect.strength)
+            .fold(0.0, f64::max);
+
+        1.0 - slow
+    }
+
+    /// Color to multiply the entity's sprite with, mixing every kind of effect it suffers.
+    pub fn tint(&self) -> Vec3 {
+        StatusKind::ALL
+            .into_iter()
+            .filter(|kind| self.has(*kind))
+            .fold(Vec3::one(), |tint, kind| tint * kind.tint())
+    }
+
+    /// Counts down every effect, reporting the damage dealt over `delta` by each one.
+    fn update(&mut self, delta: f64, mut on_damage: impl FnMut(Entity, Faction, f64)) {
+        for active in &mut self.active {
+            if active.effect.kind.deals_damage() {
+                let damage = active.effect.strength * delta.min(active.remaining_time);
+                on_damage(active.source, active.faction, damage);
+            }
+
+            active.remaining_time -= delta;
+        }
+
+        self.active.retain(|active| active.remaining_time > 0.0);
+    }
+}
+
+impl Component for StatusEffects {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// A tick of damage dealt by a burn or poison.
+struct DamageTick {
+    target: Entity,
+    source: Entity,
+    faction: Faction,
+    damage: f64,
+}
+
+pub struct UpdateStatusEffects;
+
+#[derive(SystemData)]
+pub struct UpdateStatusEffectsData<'a> {
+    entities: Entities<'a>,
+    delta: ReadExpect<'a, Delta>,
+    hits: HitData<'a>,
+}
+
+impl<'a> System<'a> for UpdateStatusEffects {
+    type SystemData = UpdateStatusEffectsData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        let mut ticks = Vec::new();
+        let mut expired = Vec::new();
+
+        let iter = (&data.entities, &mut data.hits.status_effects).join();
+        for (target, status_effects) in iter {
+            status_effects.update(data.delta.0, |source, faction, damage| {
+                ticks.push(DamageTick {
+                    target,
+                    source,
+                    faction,
+                    damage,
+                });
+            });
+
+            if status_effects.active.is_empty() {
+                expired.push(target);
+            }
+        }
+
+        for entity in expired {
+            data.hits.status_effects.remove(entity);
+        }
+
+        for tick in ticks {
+            if !data.hits.is_dead(tick.target) {
+                data.hits.apply_damage_over_time(
+                    tick.source,
+                    tick.faction,
+                    tick.damage,
+                    tick.target,
+                );
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::game::{ControlledPlayer, Delta, Timestamp};
+use crate::game::{ControlledPlayers, Delta, Timestamp};
 use specs::prelude::*;
 use specs::{Component, Entity, ReadStorage, World, WorldExt, WriteStorage};
 use std::ops::{Deref, DerefMut};
@@ -8,21 +8,18 @@ pub trait WorldExtensions {
 
     fn delta(&self) -> f64;
 
-    fn controlled_player(&self) -> Option<Entity>;
+    fn controlled_player(&self, index: usize) -> Option<Entity>;
+
+    fn controlled_players(&self) -> Vec<Entity>;
 
     fn unwrap_read<T: Component>(&self, entity: Entity) -> UnwrapRead<T>;
 
     fn unwrap_write<T: Component>(&self, entity: Entity) -> UnwrapWrite<T>;
 
-    fn controlled_player_read<T: Component>(&self) -> Option<UnwrapRead<T>> {
-        self.controlled_player()
+    fn controlled_player_read<T: Component>(&self, index: usize) -> Option<UnwrapRead<T>> {
+        self.controlled_player(index)
             .map(|player| self.unwrap_read::<T>(player))
     }
-
-    fn controlled_player_write<T: Component>(&self) -> Option<UnwrapWrite<T>> {
-        self.controlled_player()
-            .map(|player| self.unwrap_write::<T>(player))
-    }
 }
 
 pub struct UnwrapRead<'a, T: Component> {
@@ -66,8 +63,12 @@ impl WorldExtensions for World {
         self.fetch::<Delta>().0
     }
 
-    fn controlled_player(&self) -> Option<Entity> {
-        self.try_fetch::<ControlledPlayer>().map(|player| player.0)
+    fn controlled_player(&self, index: usize) -> Option<Entity> {
+        self.fetch::<ControlledPlayers>().entity(index)
+    }
+
+    fn controlled_players(&self) -> Vec<Entity> {
+        self.fetch::<ControlledPlayers>().entities().collect()
     }
 
     fn unwrap_read<T: Component>(&self, entity: Entity) -> UnwrapRead<T> {
@@ -86,26 +87,30 @@ impl WorldExtensions for World {
 }
 
 #[derive(SystemData)]
-pub struct ReadControlledPlayer<'a> {
-    controlled_player: Option<ReadExpect<'a, ControlledPlayer>>,
+pub struct ReadControlledPlayers<'a> {
+    controlled_players: ReadExpect<'a, ControlledPlayers>,
 }
 
-impl<'a> ReadControlledPlayer<'a> {
-    pub fn value(&self) -> Option<Entity> {
-        self.controlled_player.as_ref().map(|player| player.0)
+impl<'a> ReadControlledPlayers<'a> {
+    pub fn values(&self) -> Vec<Entity> {
+        self.controlled_players.entities().collect()
     }
 }
 
 #[derive(SystemData)]
-pub struct ReadControlledPlayerStorage<'a, T: Component> {
-    controlled_player: ReadControlledPlayer<'a>,
+pub struct ReadControlledPlayersStorage<'a, T: Component> {
+    controlled_players: ReadExpect<'a, ControlledPlayers>,
     storage: ReadStorage<'a, T>,
 }
 
-impl<'a, T: Component> ReadControlledPlayerStorage<'a, T> {
-    pub fn value(&self) -> Option<&T> {
-        self.controlled_player
-            .value()
-            .and_then(|player| self.storage.get(player))
+impl<'a, T: Component> ReadControlledPlayersStorage<'a, T> {
+    /// Components of live controlled players, paired with their local player index.
+    pub fn values(&self) -> impl Iterator<Item = (usize, &T)> + '_ {
+        (0..self.controlled_players.len()).filter_map(|index| {
+            self.controlled_players
+                .entity(index)
+                .and_then(|player| self.storage.get(player))
+                .map(|value| (index, value))
+        })
     }
 }
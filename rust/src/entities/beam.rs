@@ -0,0 +1,103 @@
+use float_ord::FloatOrd;
+use specs::{Builder, Entity, Join, World, WorldExt};
+
+use crate::audio::{play_sound, sound};
+use crate::components::bounds::Bounds;
+use crate::components::bullet::{Bullet, BulletTarget, HitData};
+use crate::components::lifetime::Lifetime;
+use crate::components::sprite::sprite::Sprite;
+use crate::components::status_effects::StatusEffect;
+use crate::graphics::texture;
+use crate::map::Map;
+use crate::util::intersection_grid::IntersectionGrid;
+use crate::util::rect::rect;
+use crate::util::vector::Vec2;
+use crate::{vec2, Mat3};
+
+const RANGE: f64 = 30.0;
+const WIDTH: f64 = 0.08;
+const DURATION: f64 = 0.1;
+
+/// Instantly hits the targets along a ray up to the first wall. Every whole point of
+/// `penetration` lets the beam pierce one more target.
+#[allow(clippy::too_many_arguments)]
+pub fn fire(
+    world: &mut World,
+    owner: Entity,
+    damage: f64,
+    knockback: f64,
+    penetration: f64,
+    effect: Option<StatusEffect>,
+    focus: Vec2,
+    direction: Vec2,
+) -> Entity {
+    let length = world
+        .fetch::<Map>()
+        .raycast(focus, direction, RANGE)
+        .map_or(RANGE, |hit| hit.distance);
+
+    let max_targets = penetration.max(0.0).floor() as usize + 1;
+    let bullet = Bullet::new(owner, damage, knockback, None).with_effect(effect);
+
+    {
+        let targets = targets_along(world, focus, direction, length);
+        let mut hits = world.system_data::<HitData>();
+
+        let alive = targets
+            .into_iter()
+            .filter(|target| !hits.is_dead(*target))
+            .take(max_targets)
+            .collect::<Vec<_>>();
+
+        for target in alive {
+            play_sound(sound::hit());
+            hits.apply_hit(bullet, direction, target);
+        }
+    }
+
+    create(world, focus, focus + direction * length)
+}
+
+/// Bullet targets crossed by the ray within `length`, nearest first.
+fn targets_along(world: &World, origin: Vec2, direction: Vec2, length: f64) -> Vec<Entity> {
+    let entities = world.entities();
+    let bounds = world.read_storage::<Bounds>();
+    let targets = world.read_storage::<BulletTarget>();
+
+    let mut grid = IntersectionGrid::new();
+    for (entity, bounds, _) in (&entities, &bounds, &targets).join() {
+        grid.insert(bounds.0, entity, ());
+    }
+
+    let end = origin + direction * length;
+    let area = rect(origin.min(end), (end - origin).abs());
+
+    let mut crossed = grid
+        .query(area)
+        .into_iter()
+        .filter_map(|(entity, (bounds, _))| {
+            let distance = bounds.raycast(origin, direction)?;
+            if distance <= length {
+                Some((*entity, distance))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    crossed.sort_by_key(|(entity, distance)| (FloatOrd(*distance), entity.id()));
+    crossed.into_iter().map(|(entity, _)| entity).collect()
+}
+
+fn create(world: &mut World, start: Vec2, end: Vec2) -> Entity {
+    let delta = end - start;
+    let sprite_transform =
+        Mat3::rotation(delta.angle()) * Mat3::scale(vec2(delta.length().max(WIDTH), WIDTH));
+
+    world
+        .create_entity()
+        .with(Bounds(rect(start.min(end), delta.abs())))
+        .with(Sprite::new_transformed(texture::bullet(), sprite_transform))
+        .with(Lifetime::new(DURATION))
+        .build()
+}
@@ -0,0 +1,56 @@
+use lazy_static::lazy_static;
+use specs::{Builder, Entity, World, WorldExt};
+
+use crate::components::boss::Boss;
+use crate::components::bounds::Bounds;
+use crate::components::bullet::BulletTarget;
+use crate::components::enemy::Enemy;
+use crate::components::health::Health;
+use crate::components::melee_attacker::MeleeAttacker;
+use crate::components::physics::{Collider, Physics};
+use crate::components::player_seeker::PlayerSeeker;
+use crate::components::sprite::character_animation::CharacterFrameSet;
+use crate::components::sprite::character_animation::{CharacterAnimation, Facing};
+use crate::graphics::texture;
+use crate::util::random::RandomStreams;
+use crate::util::rect::Rect;
+use crate::util::vector::Vec2;
+use crate::vec2;
+
+const SIZE: Vec2 = vec2(0.8, 0.95);
+const SPRITE_SIZE: Vec2 = vec2(1.25, 1.25);
+const DRAG: f64 = 8.0;
+
+const DAMAGE: f64 = 45.0;
+const COOLDOWN: f64 = 1.2;
+const DELAY: f64 = 0.3;
+
+lazy_static! {
+    static ref FRAME_SETS: [CharacterFrameSet; 2] = [
+        CharacterFrameSet::from_texture_set(Vec2::left(), texture::boss_left()),
+        CharacterFrameSet::from_texture_set(Vec2::right(), texture::boss_right()),
+    ];
+}
+
+pub fn create(world: &mut World, focus: Vec2, health: Health, speed: f64) -> Entity {
+    let seeker = PlayerSeeker::new(speed, world.fetch_mut::<RandomStreams>().ai());
+
+    world
+        .create_entity()
+        .with(Bounds(Rect::focused(focus, SIZE)))
+        .with(Facing(Vec2::zero()))
+        .with(CharacterAnimation::new_sized(
+            &*FRAME_SETS,
+            0.7,
+            SPRITE_SIZE,
+        ))
+        .with(Physics::collider(DRAG))
+        .with(Collider)
+        .with(health)
+        .with(BulletTarget)
+        .with(Enemy)
+        .with(seeker)
+        .with(MeleeAttacker::new(DAMAGE, COOLDOWN, DELAY))
+        .with(Boss::new())
+        .build()
+}
@@ -5,6 +5,7 @@ use crate::components::bullet::Bullet;
 use crate::components::health::Health;
 use crate::components::physics::Physics;
 use crate::components::sprite::sprite::Sprite;
+use crate::components::status_effects::StatusEffect;
 use crate::graphics::texture;
 use crate::util::rect::Rect;
 use crate::util::vector::Vec2;
@@ -12,27 +13,62 @@ use crate::{vec2, Mat3};
 
 const SPEED: f64 = 20.0;
 const SIZE: Vec2 = vec2(0.125, 0.125);
+const EXPLOSIVE_SIZE: Vec2 = vec2(0.2, 0.2);
 
+const HOSTILE_SPEED: f64 = 7.0;
+const HOSTILE_SIZE: Vec2 = vec2(0.2, 0.2);
+const HOSTILE_KNOCKBACK: f64 = 1.0;
+
+#[allow(clippy::too_many_arguments)]
 pub fn create(
     world: &mut World,
     owner: Entity,
     damage: f64,
     knockback: f64,
     penetration: f64,
+    explosion_radius: f64,
+    effect: Option<StatusEffect>,
     focus: Vec2,
     direction: Vec2,
 ) -> Entity {
-    let sprite_transform = Mat3::rotation(direction.angle()) * Mat3::scale(SIZE);
+    let explosion_radius = Some(explosion_radius).filter(|radius| *radius > 0.0);
+    let size = if explosion_radius.is_some() {
+        EXPLOSIVE_SIZE
+    } else {
+        SIZE
+    };
+
+    let sprite_transform = Mat3::rotation(direction.angle()) * Mat3::scale(size);
 
     let mut physics = Physics::trigger(0.0);
     *physics.velocity_mut() = direction * SPEED;
 
     world
         .create_entity()
-        .with(Bounds(Rect::focused(focus, SIZE)))
+        .with(Bounds(Rect::focused(focus, size)))
         .with(Sprite::new_transformed(texture::bullet(), sprite_transform))
         .with(physics)
-        .with(Bullet::new(owner, damage, knockback))
+        .with(Bullet::new(owner, damage, knockback, explosion_radius).with_effect(effect))
         .with(Health::full((damage * penetration).max(1.0)))
         .build()
 }
+
+pub fn create_hostile(
+    world: &mut World,
+    owner: Entity,
+    damage: f64,
+    focus: Vec2,
+    direction: Vec2,
+) -> Entity {
+    let mut physics = Physics::trigger(0.0);
+    *physics.velocity_mut() = direction * HOSTILE_SPEED;
+
+    world
+        .create_entity()
+        .with(Bounds(Rect::focused(focus, HOSTILE_SIZE)))
+        .with(Sprite::new_sized(texture::demon_bolt(), HOSTILE_SIZE))
+        .with(physics)
+        .with(Bullet::hostile(owner, damage, HOSTILE_KNOCKBACK))
+        .with(Health::full(damage.max(1.0)))
+        .build()
+}
@@ -1,45 +1,112 @@
 use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
 use specs::{Builder, Entity, World, WorldExt};
 
 use crate::components::bounds::Bounds;
 use crate::components::bullet::BulletTarget;
+use crate::components::elite::{Affix, Elite};
 use crate::components::enemy::Enemy;
 use crate::components::health::Health;
+use crate::components::lifetime::Lifetime;
 use crate::components::melee_attacker::MeleeAttacker;
 use crate::components::physics::{Collider, Physics};
 use crate::components::player_seeker::PlayerSeeker;
+use crate::components::ranged_attacker::RangedAttacker;
+use crate::components::regen::HealthRegen;
 use crate::components::spawning_demon::SpawningDemon;
 use crate::components::sprite::animation::Animation;
 use crate::components::sprite::animation::FrameSet;
 use crate::components::sprite::character_animation::CharacterFrameSet;
 use crate::components::sprite::character_animation::{CharacterAnimation, Facing};
+use crate::components::status_effects::{StatusEffect, StatusKind};
+use crate::ecs::WorldExtensions;
 use crate::graphics::texture;
-use crate::util::random::Random;
+use crate::util::random::RandomStreams;
 use crate::util::rect::Rect;
 use crate::util::vector::Vec2;
 use crate::vec2;
 
 const SIZE: Vec2 = vec2(0.333, 0.425);
+const SPLIT_SCALE: f64 = 0.7;
 const DRAG: f64 = 5.0;
 
 const DAMAGE: f64 = 25.0;
 const COOLDOWN: f64 = 1.0;
 const DELAY: f64 = 0.2;
+/// Claws fester, poisoning players for a while after each hit.
+const POISON: StatusEffect = StatusEffect::new(StatusKind::Poison, 4.0, 2.5);
+
+const RANGED_DAMAGE: f64 = 15.0;
+const RANGED_COOLDOWN: f64 = 2.5;
+const RANGED_WINDUP: f64 = 0.6;
+const RANGED_RANGE: f64 = 8.0;
+const RANGED_PREFERRED_DISTANCE: f64 = 5.0;
+
+const CHARGE_SIZE: Vec2 = vec2(0.35, 0.35);
 
 lazy_static! {
     static ref FRAME_SETS: [CharacterFrameSet; 2] = [
         CharacterFrameSet::from_texture_set(Vec2::left(), texture::demon_left()),
         CharacterFrameSet::from_texture_set(Vec2::right(), texture::demon_right()),
     ];
+    static ref RANGED_FRAME_SETS: [CharacterFrameSet; 2] = [
+        CharacterFrameSet::from_texture_set(Vec2::left(), texture::ranged_demon_left()),
+        CharacterFrameSet::from_texture_set(Vec2::right(), texture::ranged_demon_right()),
+    ];
     static ref SPAWNING_FRAMES: FrameSet = FrameSet::from_texture_set(texture::spawning_demon());
+    static ref CHARGE_FRAMES: FrameSet = FrameSet::from_texture_set(texture::demon_charge());
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DemonKind {
+    /// Chases players down and claws them.
+    Melee,
+    /// Keeps its distance and shoots at players in sight.
+    Ranged,
+}
+
+impl Default for DemonKind {
+    fn default() -> Self {
+        DemonKind::Melee
+    }
 }
 
-pub fn create_spawning(world: &mut World, focus: Vec2, health: f64, speed: f64) -> Entity {
-    let spawn_time = Random::global().next_f64_in(0.5..1.15);
+pub fn create_spawning(
+    world: &mut World,
+    kind: DemonKind,
+    focus: Vec2,
+    health: f64,
+    speed: f64,
+    elite: Option<Elite>,
+) -> Entity {
+    let spawn_time = world
+        .fetch_mut::<RandomStreams>()
+        .ai()
+        .next_f64_in(0.5..1.15);
+    create_spawning_timed(
+        world,
+        kind,
+        focus,
+        Health::full(health),
+        speed,
+        elite,
+        spawn_time,
+    )
+}
 
+pub fn create_spawning_timed(
+    world: &mut World,
+    kind: DemonKind,
+    focus: Vec2,
+    health: Health,
+    speed: f64,
+    elite: Option<Elite>,
+    spawn_time: f64,
+) -> Entity {
     world
         .create_entity()
-        .with(SpawningDemon::new(spawn_time, speed))
+        .with(SpawningDemon::new(kind, spawn_time, speed))
         .with(Bounds(Rect::focused(focus, SIZE)))
         .with(Animation::new_sized(
             &*SPAWNING_FRAMES,
@@ -47,28 +114,100 @@ pub fn create_spawning(world: &mut World, focus: Vec2, health: f64, speed: f64)
             vec2(0.5, 0.5),
         ))
         .with(Collider)
-        .with(Health::full(health))
+        .with(health)
         .with(BulletTarget)
         .with(Enemy)
+        .maybe_with(elite)
         .build()
 }
 
-pub fn create(world: &mut World, focus: Vec2, health: Health, speed: f64) -> Entity {
-    world
+pub fn create(
+    world: &mut World,
+    kind: DemonKind,
+    focus: Vec2,
+    health: Health,
+    speed: f64,
+    elite: Option<Elite>,
+) -> Entity {
+    let seeker = PlayerSeeker::new(speed, world.fetch_mut::<RandomStreams>().ai());
+    let phase = world.fetch_mut::<RandomStreams>().cosmetic().next_f64();
+
+    let regen = elite
+        .as_ref()
+        .filter(|elite| elite.has(Affix::Regenerating))
+        .map(|_| HealthRegen::new());
+
+    let builder = world
         .create_entity()
         .with(Bounds(Rect::focused(focus, SIZE)))
         .with(Facing(Vec2::zero()))
-        .with(CharacterAnimation::new_sized(
-            &*FRAME_SETS,
-            0.5,
-            vec2(0.5, 0.5),
-        ))
-        .with(PlayerSeeker::new(speed))
-        .with(MeleeAttacker::new(DAMAGE, COOLDOWN, DELAY))
         .with(Physics::collider(DRAG))
         .with(Collider)
         .with(health)
         .with(BulletTarget)
         .with(Enemy)
+        .maybe_with(regen)
+        .maybe_with(elite);
+
+    match kind {
+        DemonKind::Melee => builder
+            .with(
+                CharacterAnimation::new_sized(&*FRAME_SETS, 0.5, vec2(0.5, 0.5)).with_phase(phase),
+            )
+            .with(seeker)
+            .with(MeleeAttacker::new(DAMAGE, COOLDOWN, DELAY).with_effect(POISON)),
+        DemonKind::Ranged => builder
+            .with(
+                CharacterAnimation::new_sized(&*RANGED_FRAME_SETS, 0.5, vec2(0.5, 0.5))
+                    .with_phase(phase),
+            )
+            .with(seeker.keeping_distance(RANGED_PREFERRED_DISTANCE))
+            .with(RangedAttacker::new(
+                RANGED_DAMAGE,
+                RANGED_COOLDOWN,
+                RANGED_WINDUP,
+                RANGED_RANGE,
+            )),
+    }
+    .build()
+}
+
+/// A shrunken melee demon left behind by a splitting elite.
+pub fn create_split(world: &mut World, focus: Vec2, health: f64, speed: f64) -> Entity {
+    let size = SIZE * SPLIT_SCALE;
+    let entity = create(
+        world,
+        DemonKind::Melee,
+        focus,
+        Health::full(health),
+        speed,
+        None,
+    );
+
+    world.unwrap_write::<Bounds>(entity).0 = Rect::focused(focus, size);
+
+    let phase = world.fetch_mut::<RandomStreams>().cosmetic().next_f64();
+    let animation = CharacterAnimation::new_sized(&*FRAME_SETS, 0.5, vec2(0.5, 0.5) * SPLIT_SCALE)
+        .with_phase(phase);
+
+    world
+        .write_storage::<CharacterAnimation>()
+        .insert(entity, animation)
+        .unwrap();
+
+    entity
+}
+
+/// A growing orb telegraphing a ranged demon's shot.
+pub fn create_charge(world: &mut World, focus: Vec2, duration: f64) -> Entity {
+    create_charge_sized(world, focus, CHARGE_SIZE, duration)
+}
+
+pub fn create_charge_sized(world: &mut World, focus: Vec2, size: Vec2, duration: f64) -> Entity {
+    world
+        .create_entity()
+        .with(Bounds(Rect::focused(focus, size)))
+        .with(Animation::new_sized(&*CHARGE_FRAMES, duration, size))
+        .with(Lifetime::new(duration))
         .build()
 }
@@ -0,0 +1,26 @@
+use lazy_static::lazy_static;
+use specs::{Builder, Entity, World, WorldExt};
+
+use crate::components::bounds::Bounds;
+use crate::components::lifetime::Lifetime;
+use crate::components::sprite::animation::{Animation, FrameSet};
+use crate::graphics::texture;
+use crate::util::rect::Rect;
+use crate::util::vector::Vec2;
+
+const DURATION: f64 = 0.35;
+
+lazy_static! {
+    static ref FRAMES: FrameSet = FrameSet::from_texture_set(texture::explosion());
+}
+
+pub fn create(world: &mut World, focus: Vec2, radius: f64) -> Entity {
+    let size = Vec2::one() * radius * 2.0;
+
+    world
+        .create_entity()
+        .with(Bounds(Rect::focused(focus, size)))
+        .with(Animation::new_sized(&*FRAMES, DURATION, size))
+        .with(Lifetime::new(DURATION))
+        .build()
+}
@@ -1,3 +1,7 @@
+pub mod beam;
+pub mod boss;
 pub mod bullet;
 pub mod demon;
+pub mod explosion;
+pub mod pickup;
 pub mod player;
@@ -0,0 +1,27 @@
+use specs::{Builder, Entity, World, WorldExt};
+
+use crate::components::bounds::Bounds;
+use crate::components::lifetime::Lifetime;
+use crate::components::physics::Physics;
+use crate::components::pickup::Pickup;
+use crate::util::rect::Rect;
+use crate::util::vector::Vec2;
+use crate::vec2;
+
+const SIZE: Vec2 = vec2(0.3, 0.3);
+const DRAG: f64 = 10.0;
+const DESPAWN_TIME: f64 = 20.0;
+
+pub fn create(world: &mut World, focus: Vec2, pickup: Pickup) -> Entity {
+    create_timed(world, focus, pickup, DESPAWN_TIME)
+}
+
+pub fn create_timed(world: &mut World, focus: Vec2, pickup: Pickup, despawn_time: f64) -> Entity {
+    world
+        .create_entity()
+        .with(Bounds(Rect::focused(focus, SIZE)))
+        .with(Physics::trigger(DRAG))
+        .with(Lifetime::new(despawn_time))
+        .with(pickup)
+        .build()
+}
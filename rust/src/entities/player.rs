@@ -1,9 +1,9 @@
 use std::f64::consts::PI;
-use std::mem;
 
 use lazy_static::lazy_static;
 use specs::{Builder, Entity, World, WorldExt};
 
+use crate::audio::{play_sound, sound};
 use crate::components::bounds::Bounds;
 use crate::components::health::Health;
 use crate::components::physics::{Collider, Physics};
@@ -12,12 +12,14 @@ use crate::components::regen::HealthRegen;
 use crate::components::sprite::character_animation::{
     CharacterAnimation, CharacterFrameSet, Facing,
 };
+use crate::components::status_effects::{StatusEffect, StatusEffects};
 use crate::ecs::WorldExtensions;
-use crate::entities::bullet;
+use crate::entities::{beam, bullet};
 use crate::graphics::texture;
 use crate::input::Event;
 use crate::interaction::Interaction;
-use crate::util::random::Random;
+use crate::perk::{self, Perk};
+use crate::util::random::RandomStreams;
 use crate::util::rect::Rect;
 use crate::util::vector::Vec2;
 use crate::weapon_positioning::{player_targeting_position, weapon_nose_position};
@@ -28,6 +30,11 @@ const DRAG: f64 = 10.0;
 const HEALTH: f64 = 100.0;
 const SIZE: Vec2 = vec2(0.4, 0.5);
 const STOPPING_SPEED: f64 = 0.25;
+/// Downed players can only crawl.
+const CRAWL_SPEED: f64 = 0.5;
+const ANIMATION_DURATION: f64 = 0.5;
+/// Plays the whole roll once over the course of a dash.
+const DASH_ANIMATION_DURATION: f64 = 0.15;
 
 lazy_static! {
     static ref FRAME_SETS: [CharacterFrameSet; 10] = [
@@ -48,6 +55,14 @@ lazy_static! {
             texture::player_bottom_left_idle(),
         ),
     ];
+    static ref DOWNED_FRAME_SETS: [CharacterFrameSet; 2] = [
+        CharacterFrameSet::from_texture(Vec2::right(), texture::player_dash_right()[1]),
+        CharacterFrameSet::from_texture(Vec2::left(), texture::player_dash_left()[1]),
+    ];
+    static ref DASH_FRAME_SETS: [CharacterFrameSet; 2] = [
+        CharacterFrameSet::from_texture_set(Vec2::right(), texture::player_dash_right()),
+        CharacterFrameSet::from_texture_set(Vec2::left(), texture::player_dash_left()),
+    ];
 }
 
 pub fn create(world: &mut World, focus: Vec2) -> Entity {
@@ -59,7 +74,7 @@ pub fn create(world: &mut World, focus: Vec2) -> Entity {
         .with(Facing(Vec2::zero()))
         .with(CharacterAnimation::new_sized(
             &*FRAME_SETS,
-            0.5,
+            ANIMATION_DURATION,
             vec2(0.5, 0.5),
         ))
         .with(Physics::collider(DRAG))
@@ -68,70 +83,59 @@ pub fn create(world: &mut World, focus: Vec2) -> Entity {
         .build()
 }
 
-pub fn handle_input(world: &mut World, mouse_position: Vec2) {
-    update_player_inputs(world, mouse_position);
+pub fn handle_input(world: &mut World, player: Entity, inputs: &mut Inputs, mouse_position: Vec2) {
+    world
+        .unwrap_write::<Player>(player)
+        .update_inputs(inputs, mouse_position);
 
-    let mut inputs = world.fetch_mut::<Inputs>();
+    if world.unwrap_read::<Player>(player).is_down() {
+        let _ = inputs.poll();
+
+        crawl(world, player, inputs);
+        face_player(world, player, inputs);
+        animate(world, player);
+        return;
+    }
+
+    let source = inputs.source();
+    let movement = inputs.movement_direction();
 
     for event in inputs.poll() {
         match event {
-            Event::KeyDown { key } if key == "Space" => {
-                Interaction::attempt_interact(world);
+            Event::KeyDown { key } if source.is_interact_key(&key) => {
+                Interaction::attempt_interact(world, player);
             }
             Event::JoystickTap => {
-                Interaction::attempt_interact(world);
+                Interaction::attempt_interact(world, player);
+            }
+            Event::KeyDown { key } if source.is_dash_key(&key) => {
+                dash(world, player, movement);
+            }
+            Event::JoystickSwipe => {
+                dash(world, player, movement);
+            }
+            Event::KeyDown { key } if source.is_reload_key(&key) => {
+                world.unwrap_write::<Player>(player).reload();
             }
             Event::KeyDown { key } => {
-                if let Some(slot) = parse_digit_key(&key) {
-                    select_slot(world, slot);
+                if let Some(slot) = source.parse_slot_key(&key) {
+                    world.unwrap_write::<Player>(player).select_gun(slot);
                 }
             }
             _ => {}
         }
     }
 
-    let mouse_down = inputs.is_mouse_down();
-    mem::drop(inputs);
-
-    move_player(world);
-    face_player(world);
-
-    if mouse_down {
-        attack(world);
-    }
-}
+    move_player(world, player, inputs);
+    face_player(world, player, inputs);
+    animate(world, player);
 
-fn select_slot(world: &World, slot: usize) {
-    if let Some(mut player) = world.controlled_player_write::<Player>() {
-        player.select_gun(slot);
-    }
-}
-
-fn parse_digit_key(key: &str) -> Option<usize> {
-    key.trim_start_matches("Digit").parse().ok().map(
-        |digit: usize| {
-            if digit == 0 {
-                9
-            } else {
-                digit - 1
-            }
-        },
-    )
-}
-
-fn update_player_inputs(world: &World, mouse_position: Vec2) {
-    if let Some(mut player) = world.controlled_player_write::<Player>() {
-        let inputs = world.fetch::<Inputs>();
-        player.update_inputs(&inputs, mouse_position);
+    if inputs.is_mouse_down() {
+        attack(world, player);
     }
 }
 
-fn face_player(world: &World) {
-    let player = match world.controlled_player() {
-        Some(player) => player,
-        None => return,
-    };
-
+fn face_player(world: &World, player: Entity, inputs: &Inputs) {
     let physics = world.unwrap_read::<Physics>(player);
     let facing = &mut world.unwrap_write::<Facing>(player).0;
 
@@ -141,8 +145,6 @@ fn face_player(world: &World) {
         physics.velocity().normalize_or_zero()
     };
 
-    let inputs = world.fetch::<Inputs>();
-
     if inputs.is_mouse_down() {
         if inputs.mouse().x() * direction.x() < 0.0 {
             direction *= vec2(-1.0, 1.0);
@@ -156,20 +158,74 @@ fn face_player(world: &World) {
     *facing = direction;
 }
 
-fn move_player(world: &World) {
-    if let Some(mut physics) = world.controlled_player_write::<Physics>() {
-        let inputs = world.fetch::<Inputs>();
-        let player_direction = inputs.movement_direction();
-        physics.accelerate_to(player_direction * SPEED);
+/// Dashes towards `direction`, or keeps going the way the player is already moving.
+fn dash(world: &World, player: Entity, direction: Vec2) {
+    let direction = if direction == Vec2::zero() {
+        world
+            .unwrap_read::<Physics>(player)
+            .velocity()
+            .normalize_or_zero()
+    } else {
+        direction
+    };
+
+    if world.unwrap_write::<Player>(player).try_dash(direction) {
+        play_sound(sound::dash());
     }
 }
 
-fn attack(world: &mut World) {
-    let player = match world.controlled_player() {
-        Some(player) => player,
-        None => return,
+fn animate(world: &World, player: Entity) {
+    let player_component = world.unwrap_read::<Player>(player);
+
+    let (frame_sets, duration): (&'static [CharacterFrameSet], f64) = if player_component.is_down()
+    {
+        (&*DOWNED_FRAME_SETS, ANIMATION_DURATION)
+    } else if player_component.is_dashing() {
+        (&*DASH_FRAME_SETS, DASH_ANIMATION_DURATION)
+    } else {
+        (&*FRAME_SETS, ANIMATION_DURATION)
+    };
+
+    world
+        .unwrap_write::<CharacterAnimation>(player)
+        .set_frame_sets(frame_sets, duration);
+}
+
+fn crawl(world: &World, player: Entity, inputs: &Inputs) {
+    world
+        .unwrap_write::<Physics>(player)
+        .accelerate_to(inputs.movement_direction() * CRAWL_SPEED);
+}
+
+fn move_player(world: &World, player: Entity, inputs: &Inputs) {
+    if let Some(velocity) = world.unwrap_read::<Player>(player).dash_velocity() {
+        *world.unwrap_write::<Physics>(player).velocity_mut() = velocity;
+        return;
+    }
+
+    let player_direction = inputs.movement_direction();
+
+    let speed = if world
+        .unwrap_read::<Player>(player)
+        .has_perk(Perk::Swiftness)
+    {
+        SPEED * perk::SWIFTNESS_FACTOR
+    } else {
+        SPEED
     };
 
+    let speed = speed
+        * world
+            .read_storage::<StatusEffects>()
+            .get(player)
+            .map_or(1.0, StatusEffects::speed_factor);
+
+    world
+        .unwrap_write::<Physics>(player)
+        .accelerate_to(player_direction * speed);
+}
+
+fn attack(world: &mut World, player: Entity) {
     if let Some(attack) = AttackParameters::calculate(world, player) {
         world
             .unwrap_read::<Player>(player)
@@ -177,19 +233,37 @@ fn attack(world: &mut World) {
             .play_sound();
 
         for _ in 0..attack.bullet_count {
-            let variation = Random::global().next_f64_in(-1.0..1.0);
+            let variation = world
+                .fetch_mut::<RandomStreams>()
+                .combat()
+                .next_f64_in(-1.0..1.0);
             let inaccuracy_angle = variation * attack.inaccuracy * PI / 2.0;
             let direction = attack.direction.rotate(inaccuracy_angle);
 
-            bullet::create(
-                world,
-                player,
-                attack.damage,
-                attack.knockback,
-                attack.penetration,
-                attack.position,
-                direction,
-            );
+            if attack.hitscan {
+                beam::fire(
+                    world,
+                    player,
+                    attack.damage,
+                    attack.knockback,
+                    attack.penetration,
+                    attack.effect,
+                    attack.position,
+                    direction,
+                );
+            } else {
+                bullet::create(
+                    world,
+                    player,
+                    attack.damage,
+                    attack.knockback,
+                    attack.penetration,
+                    attack.explosion_radius,
+                    attack.effect,
+                    attack.position,
+                    direction,
+                );
+            }
         }
     }
 }
@@ -202,6 +276,9 @@ struct AttackParameters {
     pub penetration: f64,
     pub inaccuracy: f64,
     pub bullet_count: usize,
+    pub explosion_radius: f64,
+    pub hitscan: bool,
+    pub effect: Option<StatusEffect>,
 }
 
 impl AttackParameters {
@@ -223,6 +300,9 @@ impl AttackParameters {
                 penetration,
                 inaccuracy,
                 bullet_count,
+                explosion_radius,
+                hitscan,
+                effect,
             } => Some(AttackParameters {
                 position: source,
                 direction,
@@ -231,6 +311,9 @@ impl AttackParameters {
                 penetration,
                 inaccuracy,
                 bullet_count,
+                explosion_radius,
+                hitscan,
+                effect,
             }),
             AttackResult::Cant => None,
         }
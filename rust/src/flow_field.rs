@@ -0,0 +1,165 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use fxhash::FxHashMap;
+use specs::prelude::*;
+use specs::{ReadExpect, System, WriteExpect};
+
+use crate::components::bounds::Bounds;
+use crate::components::player::Player;
+use crate::game::Delta;
+use crate::map::Map;
+use crate::util::coord::{coord, Coord};
+
+/// Path cost, in tenths of a tile, beyond which the field isn't expanded.
+const MAX_COST: u32 = 600;
+const UPDATE_INTERVAL: f64 = 0.2;
+
+/// Distances from every walkable tile near the players to the closest player, shared by all
+/// `PlayerSeeker`s instead of each running its own search.
+pub struct FlowField {
+    costs: FxHashMap<Coord, u32>,
+    time_until_update: f64,
+}
+
+impl FlowField {
+    pub fn new() -> Self {
+        Self {
+            costs: FxHashMap::default(),
+            time_until_update: 0.0,
+        }
+    }
+
+    pub fn cost(&self, coord: Coord) -> Option<u32> {
+        self.costs.get(&coord).copied()
+    }
+
+    /// The neighbouring tile closest to a player, or `coord` itself if none is closer. `None` if
+    /// no player can be reached from `coord`.
+    pub fn next_step(&self, map: &Map, coord: Coord) -> Option<Coord> {
+        let cost = self.cost(coord)?;
+
+        let closest = map
+            .walkable_neighbours(coord)
+            .filter_map(|(neighbour, _)| Some((neighbour, self.cost(neighbour)?)))
+            .min_by_key(|(_, cost)| *cost);
+
+        match closest {
+            Some((neighbour, neighbour_cost)) if neighbour_cost < cost => Some(neighbour),
+            _ => Some(coord),
+        }
+    }
+
+    pub fn recompute(&mut self, map: &Map, sources: impl IntoIterator<Item = Coord>) {
+        self.costs.clear();
+
+        let mut queue = BinaryHeap::new();
+
+        for source in sources {
+            self.costs.insert(source, 0);
+            queue.push((Reverse(0), source.x(), source.y()));
+        }
+
+        while let Some((Reverse(cost), x, y)) = queue.pop() {
+            let current = coord(x, y);
+
+            if self.costs[&current] < cost {
+                continue;
+            }
+
+            for (neighbour, step) in map.walkable_neighbours(current) {
+                let neighbour_cost = cost + step;
+
+                if neighbour_cost > MAX_COST {
+                    continue;
+                }
+
+                let improved = self
+                    .cost(neighbour)
+                    .map_or(true, |existing| neighbour_cost < existing);
+
+                if improved {
+                    self.costs.insert(neighbour, neighbour_cost);
+                    queue.push((Reverse(neighbour_cost), neighbour.x(), neighbour.y()));
+                }
+            }
+        }
+    }
+}
+
+pub struct UpdateFlowField;
+
+#[derive(SystemData)]
+pub struct UpdateFlowFieldData<'a> {
+    map: ReadExpect<'a, Map>,
+    delta: ReadExpect<'a, Delta>,
+    flow_field: WriteExpect<'a, FlowField>,
+    bounds: ReadStorage<'a, Bounds>,
+    players: ReadStorage<'a, Player>,
+}
+
+impl<'a> System<'a> for UpdateFlowField {
+    type SystemData = UpdateFlowFieldData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        data.flow_field.time_until_update -= data.delta.0;
+
+        if data.flow_field.time_until_update > 0.0 {
+            return;
+        }
+
+        data.flow_field.time_until_update += UPDATE_INTERVAL;
+
+        let player_coords = (&data.bounds, &data.players)
+            .join()
+            .filter(|(_, player)| !player.is_down())
+            .map(|(bounds, _)| bounds.0.center().coord());
+
+        data.flow_field.recompute(&data.map, player_coords);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEEDS: [u32; 4] = [0, 7, 42, 3735928559];
+
+    #[test]
+    fn steps_lead_downhill_to_the_source() {
+        for seed in SEEDS {
+            let map = Map::new(seed);
+            let source = coord(0, 0);
+
+            let mut field = FlowField::new();
+            field.recompute(&map, [source]);
+
+            assert!(field.costs.len() > 1, "seed {}: field didn't expand", seed);
+
+            for (&start, &start_cost) in &field.costs {
+                assert!(start_cost <= MAX_COST);
+
+                let mut current = start;
+                let mut cost = start_cost;
+
+                while current != source {
+                    let next = field.next_step(&map, current).unwrap();
+                    let next_cost = field.cost(next).unwrap();
+
+                    assert!(
+                        next_cost < cost,
+                        "seed {}: stuck at {:?} on the way from {:?}",
+                        seed,
+                        current,
+                        start
+                    );
+
+                    current = next;
+                    cost = next_cost;
+                }
+            }
+
+            assert_eq!(field.next_step(&map, source), Some(source));
+        }
+    }
+}
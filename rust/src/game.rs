@@ -1,15 +1,25 @@
+use fxhash::FxHasher64;
 use specs::{Entity, Join, RunNow, World, WorldExt};
-use std::ops::DerefMut;
+use std::hash::Hasher;
+use std::mem;
 
 use crate::camera::Camera;
-use crate::components::bounds::Bounds;
+use crate::components::boss::{Boss, DropBossLoot, UpdateBosses};
+use crate::components::bounds::{Bounds, PreviousBounds, RecordPreviousBounds};
 use crate::components::bullet::{Bullet, BulletTarget, UpdateBullets};
+use crate::components::elite::{Elite, GenerateEliteAuras, SplitElites};
 use crate::components::enemy::{Enemy, KillLostEnemies};
 use crate::components::health::{DeleteDeadEntities, Health};
+use crate::components::lifetime::{ExpireLifetimes, Lifetime};
 use crate::components::melee_attacker::{AttackPlayers, MeleeAttacker};
 use crate::components::physics::{Collider, Physics, SimulatePhysics};
-use crate::components::player::{Player, ReduceAttackCooldowns};
+use crate::components::pickup::{CollectPickups, DropPickups, GeneratePickupSprites, Pickup};
+use crate::components::player::{
+    ExpirePowerUps, Player, ProgressReloads, ReduceAttackCooldowns, UpdateDashes,
+    UpdateDownedPlayers,
+};
 use crate::components::player_seeker::{PlayerSeeker, SeekPlayers};
+use crate::components::ranged_attacker::{RangedAttacker, ShootAtPlayers};
 use crate::components::regen::{HealthRegen, RegenerateHealth};
 use crate::components::spawning_demon::{FinishDemonSpawning, SpawningDemon};
 use crate::components::sprite::animation::{Animation, GenerateAnimationSprites};
@@ -18,15 +28,19 @@ use crate::components::sprite::character_animation::{
 };
 use crate::components::sprite::sprite::{GenerateStaticSprites, Sprite};
 use crate::components::sprite::{DrawSprites, FrameSprites};
+use crate::components::status_effects::{StatusEffects, UpdateStatusEffects};
 use crate::ecs::WorldExtensions;
 use crate::entities::player;
+use crate::flow_field::{FlowField, UpdateFlowField};
 use crate::graphics::{DrawBuffer, ResetDrawBuffer};
 use crate::gun::GunSpecGenerator;
+use crate::input::InputSource;
 use crate::interaction::{Interaction, UiText};
 use crate::map::draw::{DrawMapBase, DrawMapOverlay};
 use crate::map::Map;
 use crate::progression::{Progression, SpawnEnemies};
-use crate::util::random::Random;
+use crate::save::SaveState;
+use crate::util::random::RandomStreams;
 use crate::util::rect::Rect;
 use crate::util::vector::Vec2;
 use crate::weapon_hud::DrawWeaponHud;
@@ -34,45 +48,127 @@ use crate::weapon_positioning::DrawHeldWeapons;
 use crate::{vec2, Inputs};
 
 pub const SCREEN_HEIGHT: f64 = 7.5;
+pub const MAX_LOCAL_PLAYERS: usize = 4;
+pub const FIXED_DELTA: f64 = 1.0 / 60.0;
+
+const MAX_PENDING_STEPS: f64 = 8.0;
 
-pub struct ControlledPlayer(pub Entity);
+const FRAMING_MARGIN: f64 = 3.0;
+const MAX_FRAMING_ZOOM: f64 = 2.0;
 
 pub struct Delta(pub f64);
 
 pub struct Timestamp(pub f64);
 
-pub struct DisplayedInteraction(pub Option<Interaction>);
-
 pub struct HudEnabled(pub bool);
 
 pub struct Focus(Vec2);
 
 pub struct IsMobile(pub bool);
 
+pub struct ControlledPlayer {
+    entity: Option<Entity>,
+    inputs: Inputs,
+    focus: Focus,
+    displayed_interaction: Option<Interaction>,
+}
+
+pub struct ControlledPlayers(Vec<ControlledPlayer>);
+
+impl ControlledPlayers {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn push(&mut self, entity: Option<Entity>, source: InputSource, focus: Vec2) -> usize {
+        self.0.push(ControlledPlayer {
+            entity,
+            inputs: Inputs::with_source(source),
+            focus: Focus(focus),
+            displayed_interaction: None,
+        });
+
+        self.0.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn entity(&self, index: usize) -> Option<Entity> {
+        self.0.get(index).and_then(|player| player.entity)
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.0.iter().filter_map(|player| player.entity)
+    }
+
+    pub fn source_of(&self, entity: Entity) -> Option<InputSource> {
+        self.0
+            .iter()
+            .find(|player| player.entity == Some(entity))
+            .map(|player| player.inputs.source())
+    }
+
+    pub fn slots(&self) -> impl Iterator<Item = (Option<Entity>, InputSource)> + '_ {
+        self.0
+            .iter()
+            .map(|player| (player.entity, player.inputs.source()))
+    }
+
+    /// The center and height of a view framing every live player, zooming out as they spread apart.
+    fn framing(&self) -> (Vec2, f64) {
+        let mut focuses = self
+            .0
+            .iter()
+            .filter(|player| player.entity.is_some())
+            .map(|player| player.focus.0)
+            .collect::<Vec<_>>();
+
+        if focuses.is_empty() {
+            focuses = self.0.iter().map(|player| player.focus.0).collect();
+        }
+
+        let mut min = Vec2::one() * f64::INFINITY;
+        let mut max = Vec2::one() * f64::NEG_INFINITY;
+
+        for focus in focuses {
+            min = min.min(focus);
+            max = max.max(focus);
+        }
+
+        let spread = max - min;
+        let height = (spread.x().max(spread.y()) + FRAMING_MARGIN)
+            .clamp(SCREEN_HEIGHT, SCREEN_HEIGHT * MAX_FRAMING_ZOOM);
+
+        ((min + max) / 2.0, height)
+    }
+}
+
 pub struct Game {
     world: World,
+    accumulator: f64,
+    interpolation: f64,
 }
 
 impl Game {
     pub fn new(seed: u32, is_mobile: bool) -> Self {
-        *Random::global() = Random::new(seed);
-
         let mut world = World::new();
 
-        world.insert(Inputs::new());
+        world.insert(RandomStreams::new(seed));
         world.insert(Map::new(seed));
         world.insert(Timestamp(0.0));
         world.insert(Progression::new());
+        world.insert(FlowField::new());
         world.insert(GunSpecGenerator::new());
         world.insert(FrameSprites::new());
         world.insert(DrawBuffer::new());
         world.insert(Camera::new(Rect::focused(Vec2::zero(), Vec2::one())));
-        world.insert(DisplayedInteraction(None));
         world.insert(HudEnabled(false));
-        world.insert(Focus(vec2(0.5, 0.5)));
         world.insert(IsMobile(is_mobile));
 
         world.register::<Bounds>();
+        world.register::<PreviousBounds>();
         world.register::<Sprite>();
         world.register::<Animation>();
         world.register::<CharacterAnimation>();
@@ -84,83 +180,292 @@ impl Game {
         world.register::<HealthRegen>();
         world.register::<PlayerSeeker>();
         world.register::<MeleeAttacker>();
+        world.register::<RangedAttacker>();
+        world.register::<Boss>();
+        world.register::<Elite>();
+        world.register::<Pickup>();
         world.register::<Player>();
         world.register::<Enemy>();
         world.register::<Bullet>();
         world.register::<BulletTarget>();
         world.register::<SpawningDemon>();
+        world.register::<Lifetime>();
+        world.register::<StatusEffects>();
+
+        let source = if is_mobile {
+            InputSource::Touch
+        } else {
+            InputSource::Keyboard
+        };
 
         let player = player::create(&mut world, vec2(0.5, 0.5));
-        world.insert(ControlledPlayer(player));
 
-        Game { world }
+        let mut controlled_players = ControlledPlayers::new();
+        controlled_players.push(Some(player), source, vec2(0.5, 0.5));
+        world.insert(controlled_players);
+
+        Game {
+            world,
+            accumulator: 0.0,
+            interpolation: 1.0,
+        }
+    }
+
+    /// Banks `elapsed` seconds of real time, returning how many fixed steps of `FIXED_DELTA` are
+    /// now due. Whatever is left over decides how far between steps the next draw interpolates.
+    pub fn accumulate(&mut self, elapsed: f64) -> usize {
+        self.accumulator = (self.accumulator + elapsed).min(FIXED_DELTA * MAX_PENDING_STEPS);
+
+        let steps = (self.accumulator / FIXED_DELTA).floor() as usize;
+        self.accumulator -= steps as f64 * FIXED_DELTA;
+        self.interpolation = self.accumulator / FIXED_DELTA;
+
+        steps
+    }
+
+    pub fn advance(&mut self, elapsed: f64) {
+        for _ in 0..self.accumulate(elapsed) {
+            self.tick(FIXED_DELTA);
+        }
     }
 
     pub fn tick(&mut self, delta: f64) {
         self.world.insert(Delta(delta));
         self.world.fetch_mut::<Timestamp>().0 += delta;
 
+        RecordPreviousBounds.run_now(&self.world);
+
         ReduceAttackCooldowns.run_now(&self.world);
+        ExpirePowerUps.run_now(&self.world);
+        UpdateDashes.run_now(&self.world);
+        ProgressReloads.run_now(&self.world);
 
-        self.world.fetch_mut::<DisplayedInteraction>().0 = Interaction::current(&self.world);
+        self.update_displayed_interactions();
         self.handle_input();
 
         RegenerateHealth.run_now(&self.world);
+        UpdateFlowField.run_now(&self.world);
         SeekPlayers.run_now(&self.world);
         AttackPlayers.run_now(&self.world);
+        ShootAtPlayers.run_now(&self.world);
+        UpdateBosses.run_now(&self.world);
         SimulatePhysics.run_now(&self.world);
         UpdateBullets.run_now(&self.world);
+        UpdateStatusEffects.run_now(&self.world);
+        DropBossLoot.run_now(&self.world);
+        SplitElites.run_now(&self.world);
+        DropPickups.run_now(&self.world);
+        CollectPickups.run_now(&self.world);
         KillLostEnemies.run_now(&self.world);
         SpawnEnemies.run_now(&self.world);
         FinishDemonSpawning.run_now(&self.world);
+        ExpireLifetimes.run_now(&self.world);
+        UpdateDownedPlayers.run_now(&self.world);
         DeleteDeadEntities.run_now(&self.world);
 
         self.world.maintain();
-        self.maintain_controlled_player();
+        self.maintain_controlled_players();
+
+        self.update_focus();
+    }
+
+    fn update_focus(&mut self) {
+        let bounds = self.world.read_storage::<Bounds>();
+        let mut controlled_players = self.world.fetch_mut::<ControlledPlayers>();
 
-        if let Some(bounds) = self.world.controlled_player_read::<Bounds>() {
-            self.world.fetch_mut::<Focus>().0 = bounds.0.center();
+        for controlled in &mut controlled_players.0 {
+            if let Some(bounds) = controlled.entity.and_then(|player| bounds.get(player)) {
+                controlled.focus.0 = bounds.0.center();
+            }
         }
     }
 
+    fn update_displayed_interactions(&mut self) {
+        let interactions = (0..self.player_count())
+            .map(|index| {
+                self.world
+                    .controlled_player(index)
+                    .and_then(|player| Interaction::current(&self.world, player))
+            })
+            .collect::<Vec<_>>();
+
+        let mut controlled_players = self.world.fetch_mut::<ControlledPlayers>();
+
+        for (controlled, interaction) in controlled_players.0.iter_mut().zip(interactions) {
+            controlled.displayed_interaction = interaction;
+        }
+    }
+
+    pub fn save_state(&self) -> String {
+        SaveState::capture(&self.world).to_json()
+    }
+
+    pub fn load_state(&mut self, json: &str) -> Result<(), String> {
+        let state = SaveState::from_json(json)?;
+
+        let is_mobile = self.world.fetch::<IsMobile>().0;
+        let hud_enabled = self.world.fetch::<HudEnabled>().0;
+
+        let mut game = Game::new(state.seed(), is_mobile);
+        game.world.fetch_mut::<HudEnabled>().0 = hud_enabled;
+
+        for player in game.world.controlled_players() {
+            let _ = game.world.delete_entity(player);
+        }
+
+        state.restore(&mut game.world);
+        game.world.maintain();
+        game.update_focus();
+
+        *self = game;
+        log::info!("Loaded saved game");
+
+        Ok(())
+    }
+
+    /// Hashes the simulation state so that peers fed the same inputs can detect divergence.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = FxHasher64::default();
+        hasher.write(SaveState::capture(&self.world).to_json().as_bytes());
+
+        let bounds = self.world.read_storage::<Bounds>();
+        let physicses = self.world.read_storage::<Physics>();
+
+        for (bounds, physics) in (&bounds, (&physicses).maybe()).join() {
+            let velocity = physics.map(Physics::velocity).unwrap_or_else(Vec2::zero);
+
+            for component in [bounds.0.position, velocity]
+                .iter()
+                .flat_map(Vec2::components)
+            {
+                hasher.write_u64(component.to_bits());
+            }
+        }
+
+        hasher.finish()
+    }
+
     pub fn enable_hud(&mut self) {
         self.world.fetch_mut::<HudEnabled>().0 = true;
     }
 
     pub fn is_over(&self) -> bool {
-        self.world.controlled_player().is_none()
+        self.world.controlled_players().is_empty()
     }
 
-    pub fn inputs(&mut self) -> impl DerefMut<Target = Inputs> + '_ {
-        self.world.fetch_mut()
+    /// Adds another local player next to the first live one, returning its index.
+    pub fn add_player(&mut self, source: InputSource) -> Option<usize> {
+        if self.player_count() >= MAX_LOCAL_PLAYERS {
+            return None;
+        }
+
+        let position = match self.world.controlled_players().first() {
+            Some(player) => self.world.unwrap_read::<Bounds>(*player).0.center(),
+            None => vec2(0.5, 0.5),
+        };
+
+        let player = player::create(&mut self.world, position);
+
+        let index =
+            self.world
+                .fetch_mut::<ControlledPlayers>()
+                .push(Some(player), source, position);
+
+        log::info!("Added local player {}", index);
+        Some(index)
     }
 
-    fn handle_input(&mut self) {
-        let focus = self.world.fetch::<Focus>().0;
-        let mouse = self.world.fetch::<Inputs>().mouse();
-        let mouse_position = mouse * SCREEN_HEIGHT / 2.0 + focus;
-        player::handle_input(&mut self.world, mouse_position);
+    pub fn player_count(&self) -> usize {
+        self.world.fetch::<ControlledPlayers>().len()
     }
 
-    fn maintain_controlled_player(&mut self) {
-        let should_delete = match self.world.controlled_player() {
-            Some(player) => !self.world.is_alive(player),
-            None => false,
-        };
+    pub fn is_player_alive(&self, player: usize) -> bool {
+        self.world.controlled_player(player).is_some()
+    }
 
-        if should_delete {
-            log::info!("Removed player from game");
-            self.world.remove::<ControlledPlayer>();
+    /// Remaining bleed-out time of a downed player, relative to the full duration.
+    pub fn bleed_out(&self, player: usize) -> Option<f64> {
+        self.world
+            .controlled_player_read::<Player>(player)
+            .and_then(|player| player.bleed_out())
+    }
+
+    pub fn inputs(&mut self, player: usize) -> Option<&mut Inputs> {
+        self.controlled_players_mut()
+            .0
+            .get_mut(player)
+            .map(|controlled| &mut controlled.inputs)
+    }
+
+    pub fn all_inputs(&mut self) -> impl Iterator<Item = &mut Inputs> {
+        self.controlled_players_mut()
+            .0
+            .iter_mut()
+            .map(|controlled| &mut controlled.inputs)
+    }
+
+    fn controlled_players_mut(&mut self) -> &mut ControlledPlayers {
+        self.world.get_mut::<ControlledPlayers>().unwrap()
+    }
+
+    fn handle_input(&mut self) {
+        let (center, height) = self.world.fetch::<ControlledPlayers>().framing();
+
+        for index in 0..self.player_count() {
+            let controlled = &mut self.controlled_players_mut().0[index];
+            let entity = controlled.entity;
+            let focus = controlled.focus.0;
+            let mut inputs = mem::take(&mut controlled.inputs);
+
+            match entity {
+                Some(player) => {
+                    let mouse_position = if inputs.source().aims_from_player() {
+                        inputs.mouse() * SCREEN_HEIGHT / 2.0 + focus
+                    } else {
+                        inputs.mouse() * height / 2.0 + center
+                    };
+
+                    player::handle_input(&mut self.world, player, &mut inputs, mouse_position);
+                }
+                None => {
+                    let _ = inputs.poll();
+                }
+            }
+
+            self.controlled_players_mut().0[index].inputs = inputs;
+        }
+    }
+
+    fn maintain_controlled_players(&mut self) {
+        let entities = self.world.entities();
+        let mut controlled_players = self.world.fetch_mut::<ControlledPlayers>();
+
+        for (index, controlled) in controlled_players.0.iter_mut().enumerate() {
+            let should_remove = match controlled.entity {
+                Some(player) => !entities.is_alive(player),
+                None => false,
+            };
+
+            if should_remove {
+                log::info!("Removed player {} from game", index);
+                controlled.entity = None;
+                controlled.displayed_interaction = None;
+            }
         }
     }
 
     pub fn draw(&mut self, aspect_ratio: f64) -> *const u8 {
+        let current_bounds = self.interpolate_bounds(self.interpolation);
+        self.update_focus();
+
         *self.world.fetch_mut() = self.camera(aspect_ratio);
 
         ResetDrawBuffer.run_now(&self.world);
         DrawMapBase.run_now(&self.world);
         FaceToVelocities.run_now(&self.world);
         GenerateStaticSprites.run_now(&self.world);
+        GeneratePickupSprites.run_now(&self.world);
+        GenerateEliteAuras.run_now(&self.world);
         GenerateAnimationSprites.run_now(&self.world);
         GenerateCharacterAnimationSprites.run_now(&self.world);
         DrawHeldWeapons.run_now(&self.world);
@@ -168,13 +473,41 @@ impl Game {
         DrawMapOverlay.run_now(&self.world);
         DrawWeaponHud.run_now(&self.world);
 
+        self.restore_bounds(current_bounds);
+        self.update_focus();
+
         self.world.fetch::<DrawBuffer>().as_ptr()
     }
 
+    fn interpolate_bounds(&mut self, alpha: f64) -> Vec<(Entity, Rect)> {
+        let entities = self.world.entities();
+        let mut bounds = self.world.write_storage::<Bounds>();
+        let previous_bounds = self.world.read_storage::<PreviousBounds>();
+
+        let mut current_bounds = Vec::new();
+
+        for (entity, bounds, previous) in (&entities, &mut bounds, &previous_bounds).join() {
+            current_bounds.push((entity, bounds.0));
+            bounds.0 = previous.0.interpolate(bounds.0, alpha);
+        }
+
+        current_bounds
+    }
+
+    fn restore_bounds(&mut self, current_bounds: Vec<(Entity, Rect)>) {
+        let mut bounds = self.world.write_storage::<Bounds>();
+
+        for (entity, rect) in current_bounds {
+            if let Some(bounds) = bounds.get_mut(entity) {
+                bounds.0 = rect;
+            }
+        }
+    }
+
     fn camera(&self, aspect_ratio: f64) -> Camera {
-        let focus = self.world.fetch::<Focus>().0;
-        let size = vec2(aspect_ratio, 1.0) * SCREEN_HEIGHT;
-        Camera::new(Rect::focused(focus, size))
+        let (center, height) = self.world.fetch::<ControlledPlayers>().framing();
+        let size = vec2(aspect_ratio, 1.0) * height;
+        Camera::new(Rect::focused(center, size))
     }
 
     pub fn entity_count(&self) -> usize {
@@ -185,47 +518,87 @@ impl Game {
         self.world.fetch::<Progression>().round()
     }
 
-    pub fn credits(&self) -> Option<usize> {
+    /// The remaining health of the current boss, relative to its maximum.
+    pub fn boss_health(&self) -> Option<f64> {
+        let healths = self.world.read_storage::<Health>();
+        let bosses = self.world.read_storage::<Boss>();
+
+        (&healths, &bosses)
+            .join()
+            .map(|(health, _)| health.remaining_relative())
+            .next()
+    }
+
+    pub fn credits(&self, player: usize) -> Option<usize> {
         self.world
-            .controlled_player_read::<Player>()
+            .controlled_player_read::<Player>(player)
             .map(|player| player.credits())
     }
 
-    pub fn interaction_heading(&self) -> String {
-        let default = UiText::new();
-        let interaction = self.world.fetch::<DisplayedInteraction>();
-
-        let heading = interaction
-            .0
-            .as_ref()
-            .map(Interaction::heading)
-            .unwrap_or(&default);
+    pub fn interaction_heading(&self, player: usize) -> String {
+        self.displayed_interaction_text(player, Interaction::heading)
+    }
 
-        serde_json::to_string(heading).unwrap()
+    pub fn interaction_caption(&self, player: usize) -> String {
+        self.displayed_interaction_text(player, Interaction::caption)
     }
 
-    pub fn interaction_caption(&self) -> String {
+    fn displayed_interaction_text(
+        &self,
+        player: usize,
+        text: impl FnOnce(&Interaction) -> &UiText,
+    ) -> String {
         let default = UiText::new();
-        let interaction = self.world.fetch::<DisplayedInteraction>();
+        let controlled_players = self.world.fetch::<ControlledPlayers>();
 
-        let heading = interaction
+        let text = controlled_players
             .0
-            .as_ref()
-            .map(Interaction::caption)
+            .get(player)
+            .and_then(|controlled| controlled.displayed_interaction.as_ref())
+            .map(text)
             .unwrap_or(&default);
 
-        serde_json::to_string(heading).unwrap()
+        serde_json::to_string(text).unwrap()
     }
 
-    pub fn current_ammo(&self) -> Option<usize> {
+    pub fn current_ammo(&self, player: usize) -> Option<usize> {
         self.world
-            .controlled_player_read::<Player>()
+            .controlled_player_read::<Player>(player)
             .map(|player| player.selected_gun().current_ammo())
     }
 
-    pub fn max_ammo(&self) -> Option<usize> {
+    pub fn max_ammo(&self, player: usize) -> Option<usize> {
         self.world
-            .controlled_player_read::<Player>()
+            .controlled_player_read::<Player>(player)
             .map(|player| player.selected_gun().max_ammo())
     }
+
+    pub fn reserve_ammo(&self, player: usize) -> Option<usize> {
+        self.world
+            .controlled_player_read::<Player>(player)
+            .map(|player| player.selected_gun().reserve_ammo())
+    }
+
+    pub fn reload_progress(&self, player: usize) -> Option<f64> {
+        self.world
+            .controlled_player_read::<Player>(player)
+            .and_then(|player| player.selected_gun().reload_progress())
+    }
+
+    pub fn dash_cooldown(&self, player: usize) -> Option<f64> {
+        self.world
+            .controlled_player_read::<Player>(player)
+            .map(|player| player.dash_cooldown())
+    }
+
+    /// The player's owned perks as a JSON array, in the order they were bought.
+    pub fn owned_perks(&self, player: usize) -> String {
+        let perks = self
+            .world
+            .controlled_player_read::<Player>(player)
+            .map(|player| player.perks().to_vec())
+            .unwrap_or_default();
+
+        serde_json::to_string(&perks).unwrap()
+    }
 }
@@ -1,6 +1,6 @@
 use crate::camera::Camera;
 use crate::components::health::Health;
-use crate::ecs::ReadControlledPlayerStorage;
+use crate::ecs::ReadControlledPlayersStorage;
 use crate::game::Timestamp;
 use crate::util::vector::{vec3, Vec3, Vector};
 use crate::Mat3;
@@ -26,9 +26,15 @@ impl DrawBuffer {
     }
 
     pub fn push(&mut self, entity: EntityRendererSettings) {
+        self.push_tinted(entity, Vec3::one());
+    }
+
+    /// Pushes an entity whose texture colors are multiplied by `tint`.
+    pub fn push_tinted(&mut self, entity: EntityRendererSettings, tint: Vec3) {
         self.increment_size();
         self.write_matrix(entity.transform);
         self.write_matrix(entity.uv_transform);
+        self.write_vector(tint);
     }
 
     fn write_matrix(&mut self, matrix: Mat3) {
@@ -103,7 +109,7 @@ pub struct ResetDrawBufferData<'a> {
     timestamp: ReadExpect<'a, Timestamp>,
     camera: ReadExpect<'a, Camera>,
     buffer: WriteExpect<'a, DrawBuffer>,
-    health: ReadControlledPlayerStorage<'a, Health>,
+    healths: ReadControlledPlayersStorage<'a, Health>,
 }
 
 impl<'a> System<'a> for ResetDrawBuffer {
@@ -111,10 +117,10 @@ impl<'a> System<'a> for ResetDrawBuffer {
 
     fn run(&mut self, mut data: Self::SystemData) {
         let health = data
-            .health
-            .value()
-            .map(|healt| healt.remaining_relative())
-            .unwrap_or(1.0);
+            .healths
+            .values()
+            .map(|(_, health)| health.remaining_relative())
+            .fold(1.0, f64::min);
         let (vignette_color, vignette_scale) = Self::vignette(health, data.timestamp.0);
 
         let frame = FrameSettings {
@@ -130,11 +136,20 @@ impl<'a> System<'a> for ResetDrawBuffer {
 
 #[allow(dead_code)]
 pub mod texture {
-    use crate::{vec2, Mat3};
+    use crate::{vec2, Mat3, Vec2};
 
     const TEXTURE_PIXELS: f64 = 32.0;
     const SHEET_PIXELS: f64 = 512.0;
 
+    /// Builds the transform for a region of the sprite sheet given in tiles, for textures
+    /// described by data rather than declared here.
+    pub fn from_tiles(position: Vec2, size: Vec2) -> Mat3 {
+        Mat3::transform(
+            position * TEXTURE_PIXELS / SHEET_PIXELS,
+            size * TEXTURE_PIXELS / SHEET_PIXELS,
+        )
+    }
+
     macro_rules! texture {
         ($name:ident, $x:literal, $y:literal, $width:literal, $height:literal) => {
             pub fn $name() -> Mat3 {
@@ -206,24 +221,47 @@ pub mod texture {
     texture!(player_top_right_idle, 12.5, 7.5, 0.5, 0.5);
     texture!(player_bottom_left_idle, 13.0, 7.5, 0.5, 0.5);
     texture!(player_bottom_right_idle, 13.5, 7.5, 0.5, 0.5);
+    texture_set!(player_dash_right, 4, 0.0, 14.0, 0.5, 0.5);
+    texture_set!(player_dash_left, 4, 2.0, 14.0, 0.5, 0.5);
 
     texture_set!(demon_left, 4, 0.0, 8.0, 0.5, 0.5);
     texture_set!(demon_right, 4, 2.0, 8.0, 0.5, 0.5);
 
     texture_set!(spawning_demon, 6, 4.0, 8.0, 0.5, 0.5);
 
+    texture_set!(ranged_demon_left, 4, 7.0, 8.0, 0.5, 0.5);
+    texture_set!(ranged_demon_right, 4, 9.0, 8.0, 0.5, 0.5);
+    texture!(demon_bolt, 11.0, 8.0, 0.25, 0.25);
+    texture_set!(demon_charge, 4, 11.5, 8.0, 0.5, 0.5);
+
+    texture_set!(boss_left, 4, 0.0, 13.0, 0.5, 0.5);
+    texture_set!(boss_right, 4, 2.0, 13.0, 0.5, 0.5);
+
+    texture_set!(elite_aura, 5, 0.0, 13.5, 0.5, 0.5);
+
+    texture!(ammo_pickup, 2.5, 13.5, 0.5, 0.5);
+    texture!(health_pickup, 3.0, 13.5, 0.5, 0.5);
+    texture!(credits_pickup, 3.5, 13.5, 0.5, 0.5);
+    texture!(damage_power_up, 4.0, 13.5, 0.5, 0.5);
+    texture!(rapid_fire_power_up, 4.5, 13.5, 0.5, 0.5);
+
+    texture!(vitality_perk, 5.0, 13.5, 0.5, 0.5);
+    texture!(quick_recovery_perk, 5.5, 13.5, 0.5, 0.5);
+    texture!(extra_slot_perk, 6.0, 13.5, 0.5, 0.5);
+    texture!(quick_reload_perk, 6.5, 13.5, 0.5, 0.5);
+    texture!(swiftness_perk, 7.0, 13.5, 0.5, 0.5);
+
+    texture!(enhance_upgrade, 7.5, 13.5, 0.5, 0.5);
+    texture!(refine_upgrade, 8.0, 13.5, 0.5, 0.5);
+    texture!(reroll_upgrade, 8.5, 13.5, 0.5, 0.5);
+
     texture!(bullet, 0.0, 8.5, 0.25, 0.25);
 
+    texture_set!(explosion, 5, 0.0, 12.0);
+
     texture!(closed_chest, 1.5, 7.0, 0.5, 0.5);
     texture!(open_chest, 2.0, 7.0, 0.5, 0.5);
 
-    texture!(pistol, 0.0, 9.0, 0.75, 0.75);
-    texture!(lmg, 0.75, 9.0, 0.75, 0.75);
-    texture!(shotgun, 1.5, 9.0, 0.75, 0.75);
-    texture!(rifle, 2.25, 9.0, 0.75, 0.75);
-    texture!(sniper, 3.0, 9.0, 0.75, 0.75);
-    texture!(smg, 3.75, 9.0, 0.75, 0.75);
-
     texture!(gun_hud_background_inactive, 0.0, 10.0, 2.0, 2.0);
     texture!(gun_hud_background_active, 2.0, 10.0, 2.0, 2.0);
 }
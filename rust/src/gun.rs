@@ -1,38 +1,130 @@
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
 
 use crate::audio::{play_sound, sound};
-use fxhash::FxHashMap;
+use crate::components::status_effects::StatusEffect;
+use fxhash::{FxHashMap, FxHashSet};
 use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
 
-use crate::graphics::texture;
 use crate::util::random::Random;
-use crate::util::vector::vec2;
 use crate::util::vector::Vec2;
 use crate::Mat3;
 
+/// Most modifiers a gun can carry, counting the one it was generated with.
+const MAX_MODIFIERS: usize = 3;
+/// Quality gained per refinement, worth as much as about 17 tiles of distance from the origin.
+const QUALITY_TIER: f64 = 0.25;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Gun {
     spec: Rc<GunSpec>,
-    ammo: usize,
+    magazine: usize,
+    reserve: usize,
+    reloading: Option<f64>,
 }
 
 impl Gun {
     pub fn new(spec: Rc<GunSpec>) -> Self {
+        let magazine = spec.magazine_size.min(spec.ammo);
+
         Self {
-            ammo: spec.ammo,
+            magazine,
+            reserve: spec.ammo - magazine,
+            reloading: None,
             spec,
         }
     }
 
-    pub fn current_ammo(&self) -> usize {
-        self.ammo
+    /// Swaps in an upgraded spec, keeping as much of the loaded and reserve ammo as still fits.
+    pub fn upgrade(&mut self, spec: Rc<GunSpec>) {
+        self.cancel_reload();
+
+        self.magazine = self.magazine.min(spec.magazine_size).min(spec.ammo);
+        self.reserve = self.reserve.min(spec.ammo - self.magazine);
+        self.spec = spec;
     }
 
-    pub fn current_ammo_mut(&mut self) -> &mut usize {
-        &mut self.ammo
+    /// Rounds left in the magazine.
+    pub fn current_ammo(&self) -> usize {
+        self.magazine
     }
 
     pub fn max_ammo(&self) -> usize {
-        self.spec.ammo
+        self.spec.magazine_size
+    }
+
+    pub fn reserve_ammo(&self) -> usize {
+        self.reserve
+    }
+
+    /// Rounds spent since the gun was last full.
+    pub fn missing_ammo(&self) -> usize {
+        self.spec.ammo - self.magazine - self.reserve
+    }
+
+    pub fn refill(&mut self) {
+        self.reserve = self.spec.ammo - self.magazine;
+    }
+
+    /// Adds up to `amount` rounds to the reserve, without exceeding the gun's capacity.
+    pub fn restock(&mut self, amount: usize) {
+        self.reserve += amount.min(self.missing_ammo());
+    }
+
+    pub fn can_fire(&self) -> bool {
+        self.magazine > 0 && self.reloading.is_none()
+    }
+
+    /// Spends a round, starting to reload as soon as the magazine runs dry.
+    pub fn fire(&mut self) {
+        debug_assert!(self.can_fire());
+
+        self.magazine -= 1;
+
+        if self.magazine == 0 {
+            self.start_reload();
+        }
+    }
+
+    pub fn can_reload(&self) -> bool {
+        self.reloading.is_none() && self.magazine < self.spec.magazine_size && self.reserve > 0
+    }
+
+    pub fn start_reload(&mut self) {
+        if self.can_reload() {
+            self.reloading = Some(0.0);
+        }
+    }
+
+    /// Abandons a reload in progress, losing its progress but none of the ammo.
+    pub fn cancel_reload(&mut self) {
+        self.reloading = None;
+    }
+
+    pub fn update_reload(&mut self, delta: f64) {
+        let elapsed = match &mut self.reloading {
+            Some(elapsed) => elapsed,
+            None => return,
+        };
+
+        *elapsed += delta;
+
+        if *elapsed >= self.spec.reload_time {
+            let loaded = (self.spec.magazine_size - self.magazine).min(self.reserve);
+
+            self.magazine += loaded;
+            self.reserve -= loaded;
+            self.reloading = None;
+        }
+    }
+
+    /// How far along the current reload is, from 0 to 1, or `None` if not reloading.
+    pub fn reload_progress(&self) -> Option<f64> {
+        self.reloading
+            .map(|elapsed| (elapsed / self.spec.reload_time).min(1.0))
     }
 
     pub fn play_sound(&self) {
@@ -41,6 +133,7 @@ impl Gun {
             ArchetypeCategory::Fast => play_sound(sound::shoot_fast()),
             ArchetypeCategory::Sniper => play_sound(sound::shoot_sniper()),
             ArchetypeCategory::Shotgun => play_sound(sound::shoot_shotgun()),
+            ArchetypeCategory::Launcher => play_sound(sound::shoot_slow()),
         }
     }
 
@@ -49,6 +142,8 @@ impl Gun {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GunSpec {
     name: String,
     damage: f64,
@@ -58,34 +153,179 @@ pub struct GunSpec {
     accuracy: f64,
     bullet_count: usize,
     ammo: usize,
+    magazine_size: usize,
+    reload_time: f64,
+    #[serde(default)]
+    quality: f64,
+    #[serde(default)]
+    explosion_radius: f64,
+    #[serde(default)]
+    hitscan: bool,
+    #[serde(default)]
+    effect: Option<StatusEffect>,
+    #[serde(with = "archetype_name")]
     archetype: &'static GunArchetype,
+    /// The name without any modifier prefixes. Empty for guns saved before upgrades existed,
+    /// whose modifiers weren't recorded.
+    #[serde(default)]
+    base_name: String,
+    #[serde(default, with = "modifier_names")]
+    modifiers: Vec<&'static Modifier>,
+}
+
+/// A way of improving a held gun at an upgrade bench.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum GunUpgrade {
+    /// Applies another modifier on top of the existing ones.
+    Enhance,
+    /// Raises the gun's quality by a tier.
+    Refine,
+    /// Replaces the most recently applied modifier.
+    Reroll,
+}
+
+impl GunUpgrade {
+    pub const ALL: [GunUpgrade; 3] = [GunUpgrade::Enhance, GunUpgrade::Refine, GunUpgrade::Reroll];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GunUpgrade::Enhance => "Enhance",
+            GunUpgrade::Refine => "Refine",
+            GunUpgrade::Reroll => "Reroll",
+        }
+    }
 }
 
 impl GunSpec {
     pub fn starter_gun() -> Self {
-        Self {
-            name: String::from("Starter Pistol").into(),
-            damage: PISTOL.damage,
-            knockback: PISTOL.knockback,
-            cooldown: PISTOL.cooldown,
-            penetration: PISTOL.penetration,
-            accuracy: PISTOL.accuracy,
-            bullet_count: PISTOL.bullet_count,
-            ammo: PISTOL.ammo,
-            archetype: &PISTOL,
+        let catalog = catalog();
+        let archetype = catalog.starter_archetype();
+
+        Self::build(catalog.starter.name.clone(), archetype, 0.0, Vec::new())
+    }
+
+    /// Derives a spec's stats from its archetype, scaling damage with `quality` before applying
+    /// `modifiers` in order. Every modifier prefixes the name.
+    fn build(
+        base_name: String,
+        archetype: &'static GunArchetype,
+        quality: f64,
+        modifiers: Vec<&'static Modifier>,
+    ) -> Self {
+        let prefixes = modifiers
+            .iter()
+            .map(|modifier| modifier.prefix.as_str())
+            .collect::<String>();
+
+        let mut spec = Self {
+            name: format!("{}{}", prefixes, base_name),
+            damage: archetype.damage,
+            knockback: archetype.knockback,
+            cooldown: archetype.cooldown,
+            penetration: archetype.penetration,
+            accuracy: archetype.accuracy,
+            bullet_count: archetype.bullet_count,
+            ammo: archetype.ammo,
+            magazine_size: archetype.magazine_size,
+            reload_time: archetype.reload_time,
+            quality,
+            explosion_radius: archetype.explosion_radius,
+            hitscan: archetype.hitscan,
+            effect: None,
+            archetype,
+            base_name,
+            modifiers: Vec::new(),
+        };
+
+        spec.damage += spec.damage * quality;
+
+        for modifier in modifiers {
+            modifier.apply(&mut spec);
+            spec.modifiers.push(modifier);
         }
+
+        spec
+    }
+
+    /// The spec after applying `upgrade`, or `None` if it can't be applied to this gun.
+    pub fn upgraded(&self, upgrade: GunUpgrade, random: &mut Random) -> Option<Self> {
+        if self.base_name.is_empty() {
+            return None;
+        }
+
+        let mut modifiers = self.modifiers.clone();
+        let mut quality = self.quality;
+
+        match upgrade {
+            GunUpgrade::Enhance => {
+                if modifiers.len() >= MAX_MODIFIERS {
+                    return None;
+                }
+
+                modifiers.push(self.new_modifier(random)?);
+            }
+            GunUpgrade::Refine => quality += QUALITY_TIER,
+            GunUpgrade::Reroll => {
+                let new_modifier = self.new_modifier(random)?;
+                *modifiers.last_mut()? = new_modifier;
+            }
+        }
+
+        Some(Self::build(
+            self.base_name.clone(),
+            self.archetype,
+            quality,
+            modifiers,
+        ))
+    }
+
+    /// Picks a modifier the gun doesn't have yet, skipping ones without a prefix since they
+    /// don't change anything.
+    fn new_modifier(&self, random: &mut Random) -> Option<&'static Modifier> {
+        let candidates = catalog()
+            .modifiers
+            .iter()
+            .filter(|modifier| !modifier.prefix.is_empty())
+            .filter(|modifier| {
+                self.modifiers
+                    .iter()
+                    .all(|existing| existing.name != modifier.name)
+            })
+            .collect::<Vec<_>>();
+
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let index =
+            random.weighted_index(|| candidates.iter().map(|modifier| 1.0 / modifier.rarity));
+
+        Some(candidates[index])
     }
 
     pub fn dps_heuristic(&self) -> f64 {
-        self.damage / self.cooldown * self.bullet_count as f64 * self.hit_rate_heuristic()
+        self.damage
+            * self.fire_rate_heuristic()
+            * self.bullet_count as f64
+            * self.hit_rate_heuristic()
     }
 
     pub fn kbps_heuristic(&self) -> f64 {
-        self.knockback / self.cooldown * self.bullet_count as f64 * self.hit_rate_heuristic()
+        self.knockback
+            * self.fire_rate_heuristic()
+            * self.bullet_count as f64
+            * self.hit_rate_heuristic()
     }
 
     pub fn ammo_duration_heuristic(&self) -> f64 {
-        self.ammo as f64 * self.cooldown
+        let reloads = self.ammo.saturating_sub(1) / self.magazine_size;
+        self.ammo as f64 * self.cooldown + reloads as f64 * self.reload_time
+    }
+
+    /// Shots per second over a whole magazine, including the reload afterwards.
+    fn fire_rate_heuristic(&self) -> f64 {
+        let magazine = self.magazine_size as f64;
+        magazine / (magazine * self.cooldown + self.reload_time)
     }
 
     fn hit_rate_heuristic(&self) -> f64 {
@@ -128,6 +368,35 @@ impl GunSpec {
         self.ammo
     }
 
+    pub fn magazine_size(&self) -> usize {
+        self.magazine_size
+    }
+
+    pub fn reload_time(&self) -> f64 {
+        self.reload_time
+    }
+
+    /// How much stronger than its archetype the gun was generated, growing with distance from the
+    /// start.
+    pub fn quality(&self) -> f64 {
+        self.quality
+    }
+
+    /// Radius within which bullets deal falloff damage on impact, or zero if they don't explode.
+    pub fn explosion_radius(&self) -> f64 {
+        self.explosion_radius
+    }
+
+    /// Whether shots are instant beams rather than simulated bullets.
+    pub fn hitscan(&self) -> bool {
+        self.hitscan
+    }
+
+    /// The status effect inflicted on targets hit, if any.
+    pub fn effect(&self) -> Option<StatusEffect> {
+        self.effect
+    }
+
     pub fn inaccuracy(&self) -> f64 {
         1.0 / self.accuracy.max(1.0)
     }
@@ -149,6 +418,8 @@ impl GunSpec {
     }
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct GunArchetype {
     category: ArchetypeCategory,
     name: String,
@@ -160,124 +431,451 @@ struct GunArchetype {
     accuracy: f64,
     bullet_count: usize,
     ammo: usize,
+    magazine_size: usize,
+    reload_time: f64,
+    #[serde(default)]
+    explosion_radius: f64,
+    #[serde(default)]
+    hitscan: bool,
+    #[serde(deserialize_with = "catalog_units::texture")]
     texture: Mat3,
+    #[serde(deserialize_with = "catalog_units::pixels")]
     handle_offset: Vec2,
+    #[serde(deserialize_with = "catalog_units::pixels")]
     nose_offset: Vec2,
     texture_size: f64,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
 enum ArchetypeCategory {
     Slow,
     Fast,
     Sniper,
     Shotgun,
+    Launcher,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Modifier {
+    name: String,
+    #[serde(default)]
+    prefix: String,
+    rarity: f64,
+    #[serde(default)]
+    multipliers: StatMultipliers,
+    #[serde(default)]
+    caps: StatCaps,
+    /// Status effect the modifier makes bullets inflict, replacing any applied by an earlier
+    /// modifier.
+    #[serde(default)]
+    effect: Option<StatusEffect>,
+}
+
+impl Modifier {
+    fn apply(&self, spec: &mut GunSpec) {
+        let multipliers = &self.multipliers;
+
+        spec.damage *= multipliers.damage;
+        spec.knockback *= multipliers.knockback;
+        spec.cooldown *= multipliers.cooldown;
+        spec.penetration *= multipliers.penetration;
+        spec.accuracy *= multipliers.accuracy;
+        spec.bullet_count = (spec.bullet_count as f64 * multipliers.bullet_count) as usize;
+        spec.ammo = (spec.ammo as f64 * multipliers.ammo) as usize;
+        spec.magazine_size =
+            ((spec.magazine_size as f64 * multipliers.magazine_size) as usize).max(1);
+        spec.reload_time *= multipliers.reload_time;
+
+        let caps = &self.caps;
+
+        let cap = |value: &mut f64, cap: Option<f64>| {
+            if let Some(cap) = cap {
+                *value = value.min(cap);
+            }
+        };
+
+        cap(&mut spec.damage, caps.damage);
+        cap(&mut spec.knockback, caps.knockback);
+        cap(&mut spec.cooldown, caps.cooldown);
+        cap(&mut spec.penetration, caps.penetration);
+        cap(&mut spec.accuracy, caps.accuracy);
+        cap(&mut spec.reload_time, caps.reload_time);
+
+        if let Some(effect) = self.effect {
+            spec.effect = Some(effect);
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct StatMultipliers {
+    damage: f64,
+    knockback: f64,
+    cooldown: f64,
+    penetration: f64,
+    accuracy: f64,
+    bullet_count: f64,
+    ammo: f64,
+    magazine_size: f64,
+    reload_time: f64,
+}
+
+impl Default for StatMultipliers {
+    fn default() -> Self {
+        Self {
+            damage: 1.0,
+            knockback: 1.0,
+            cooldown: 1.0,
+            penetration: 1.0,
+            accuracy: 1.0,
+            bullet_count: 1.0,
+            ammo: 1.0,
+            magazine_size: 1.0,
+            reload_time: 1.0,
+        }
+    }
+}
+
+/// Upper bounds applied after a modifier's multipliers.
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct StatCaps {
+    damage: Option<f64>,
+    knockback: Option<f64>,
+    cooldown: Option<f64>,
+    penetration: Option<f64>,
+    accuracy: Option<f64>,
+    reload_time: Option<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StarterGun {
+    name: String,
+    archetype: String,
+}
+
+/// Gun archetypes and modifiers, described in `assets/guns.json` so they can be balanced without
+/// rebuilding.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GunCatalog {
+    starter: StarterGun,
+    archetypes: Vec<GunArchetype>,
+    modifiers: Vec<Modifier>,
+}
+
+impl GunCatalog {
+    fn parse(json: &str) -> Result<Self, Vec<String>> {
+        let catalog: Self = serde_json::from_str(json).map_err(|error| vec![error.to_string()])?;
+
+        let errors = catalog.validate();
+        if errors.is_empty() {
+            Ok(catalog)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.archetypes.is_empty() {
+            errors.push(String::from("at least one archetype is required"));
+        }
+
+        if self.modifiers.is_empty() {
+            errors.push(String::from("at least one modifier is required"));
+        }
+
+        let mut archetype_names = FxHashSet::default();
+
+        for archetype in &self.archetypes {
+            let mut check = |valid: bool, problem: &str| {
+                if !valid {
+                    errors.push(format!("archetype {} {}", archetype.name, problem));
+                }
+            };
+
+            check(
+                archetype_names.insert(archetype.name.as_str()),
+                "is defined more than once",
+            );
+            check(is_positive(archetype.rarity), "must have a positive rarity");
+            check(
+                is_non_negative(archetype.damage),
+                "must not have negative damage",
+            );
+            check(
+                is_non_negative(archetype.knockback),
+                "must not have negative knockback",
+            );
+            check(
+                is_positive(archetype.cooldown),
+                "must have a positive cooldown",
+            );
+            check(
+                is_non_negative(archetype.penetration),
+                "must not have negative penetration",
+            );
+            check(
+                is_positive(archetype.accuracy),
+                "must have a positive accuracy",
+            );
+            check(archetype.bullet_count > 0, "must fire at least one bullet");
+            check(archetype.ammo > 0, "must have ammo");
+            check(archetype.magazine_size > 0, "must hold at least one round");
+            check(
+                is_positive(archetype.reload_time),
+                "must have a positive reload time",
+            );
+            check(
+                is_non_negative(archetype.explosion_radius),
+                "must not have a negative explosion radius",
+            );
+            check(
+                !archetype.hitscan || archetype.explosion_radius == 0.0,
+                "must not both be hitscan and explode",
+            );
+            check(
+                is_positive(archetype.texture_size),
+                "must have a positive texture size",
+            );
+        }
+
+        if !archetype_names.contains(self.starter.archetype.as_str()) {
+            errors.push(format!(
+                "starter gun uses unknown archetype {}",
+                self.starter.archetype
+            ));
+        }
+
+        let mut modifier_names = FxHashSet::default();
+
+        for modifier in &self.modifiers {
+            let multipliers = &modifier.multipliers;
+            let caps = &modifier.caps;
+
+            let mut check = |valid: bool, problem: &str| {
+                if !valid {
+                    errors.push(format!("modifier {} {}", modifier.name, problem));
+                }
+            };
+
+            check(
+                modifier_names.insert(modifier.name.as_str()),
+                "is defined more than once",
+            );
+            check(is_positive(modifier.rarity), "must have a positive rarity");
+            check(
+                [
+                    multipliers.damage,
+                    multipliers.knockback,
+                    multipliers.cooldown,
+                    multipliers.penetration,
+                    multipliers.accuracy,
+                    multipliers.bullet_count,
+                    multipliers.ammo,
+                    multipliers.magazine_size,
+                    multipliers.reload_time,
+                ]
+                .into_iter()
+                .all(is_non_negative),
+                "must not have negative multipliers",
+            );
+            check(
+                is_positive(multipliers.cooldown),
+                "must keep a positive cooldown",
+            );
+            check(
+                is_positive(multipliers.accuracy),
+                "must keep a positive accuracy",
+            );
+            check(
+                is_positive(multipliers.reload_time),
+                "must keep a positive reload time",
+            );
+            check(
+                [
+                    caps.damage,
+                    caps.knockback,
+                    caps.cooldown,
+                    caps.penetration,
+                    caps.accuracy,
+                    caps.reload_time,
+                ]
+                .into_iter()
+                .flatten()
+                .all(is_positive),
+                "must have positive caps",
+            );
+            check(
+                modifier.effect.map_or(true, |effect| effect.is_valid()),
+                "must have a positive effect duration and a valid strength",
+            );
+        }
+
+        errors
+    }
+
+    fn starter_archetype(&'static self) -> &'static GunArchetype {
+        self.archetypes
+            .iter()
+            .find(|archetype| archetype.name == self.starter.archetype)
+            .unwrap()
+    }
+}
+
+fn is_positive(value: f64) -> bool {
+    value.is_finite() && value > 0.0
 }
 
+fn is_non_negative(value: f64) -> bool {
+    value.is_finite() && value >= 0.0
+}
+
+const BUILT_IN_CATALOG: &str = include_str!("../../assets/guns.json");
+
 lazy_static! {
-    static ref PISTOL: GunArchetype = GunArchetype {
-        category: ArchetypeCategory::Slow,
-        name: String::from("Pistol"),
-        rarity: 1.0,
-        damage: 50.0,
-        knockback: 4.0,
-        cooldown: 0.66,
-        penetration: 0.75,
-        accuracy: 50.0,
-        bullet_count: 1,
-        ammo: 75,
-        texture: texture::pistol(),
-        handle_offset: vec2(-2.5, -0.75) / 32.0,
-        nose_offset: vec2(4.0, 1.5) / 32.0,
-        texture_size: 0.375,
-    };
-    static ref LMG: GunArchetype = GunArchetype {
-        category: ArchetypeCategory::Fast,
-        name: String::from("Light Machine Gun"),
-        rarity: 1.0,
-        damage: 7.5,
-        knockback: 0.5,
-        cooldown: 0.075,
-        penetration: 0.0,
-        accuracy: 7.5,
-        bullet_count: 1,
-        ammo: 400,
-        texture: texture::lmg(),
-        handle_offset: vec2(-1.5, -0.5) / 32.0,
-        nose_offset: vec2(6.0, 1.5) / 32.0,
-        texture_size: 0.45,
-    };
-    static ref SHOTGUN: GunArchetype = GunArchetype {
-        category: ArchetypeCategory::Shotgun,
-        name: String::from("Shotgun"),
-        rarity: 1.0,
-        damage: 20.0,
-        knockback: 2.0,
-        cooldown: 1.0,
-        penetration: 0.0,
-        accuracy: 5.0,
-        bullet_count: 6,
-        ammo: 30,
-        texture: texture::shotgun(),
-        handle_offset: vec2(-4.0, -0.25) / 32.0,
-        nose_offset: vec2(6.0, 1.5) / 32.0,
-        texture_size: 0.45,
-    };
-    static ref RIFLE: GunArchetype = GunArchetype {
-        category: ArchetypeCategory::Slow,
-        name: String::from("Assault Rifle"),
-        rarity: 1.0,
-        damage: 25.0,
-        knockback: 2.0,
-        cooldown: 0.33,
-        penetration: 0.33,
-        accuracy: 35.0,
-        bullet_count: 1,
-        ammo: 150,
-        texture: texture::rifle(),
-        handle_offset: vec2(-2.5, -0.25) / 32.0,
-        nose_offset: vec2(6.0, 1.5) / 32.0,
-        texture_size: 0.5,
-    };
-    static ref SNIPER: GunArchetype = GunArchetype {
-        category: ArchetypeCategory::Sniper,
-        name: String::from("Sniper Rifle"),
-        rarity: 1.0,
-        damage: 90.0,
-        knockback: 10.0,
-        cooldown: 1.25,
-        penetration: 1.0,
-        accuracy: 66.0,
-        bullet_count: 1,
-        ammo: 30,
-        texture: texture::sniper(),
-        handle_offset: vec2(-2.0, -0.25) / 32.0,
-        nose_offset: vec2(6.0, 1.5) / 32.0,
-        texture_size: 0.5,
-    };
-    static ref SMG: GunArchetype = GunArchetype {
-        category: ArchetypeCategory::Fast,
-        name: String::from("Submachine Gun"),
-        rarity: 1.0,
-        damage: 17.5,
-        knockback: 0.0,
-        cooldown: 0.2,
-        penetration: 0.0,
-        accuracy: 15.0,
-        bullet_count: 1,
-        ammo: 250,
-        texture: texture::smg(),
-        handle_offset: vec2(0.25, -0.25) / 32.0,
-        nose_offset: vec2(6.0, 2.0) / 32.0,
-        texture_size: 0.5,
-    };
-    static ref ARCHETYPES: Vec<&'static GunArchetype> =
-        vec![&PISTOL, &LMG, &SHOTGUN, &RIFLE, &SNIPER, &SMG];
+    static ref CATALOG: RwLock<&'static GunCatalog> = RwLock::new(Box::leak(Box::new(
+        GunCatalog::parse(BUILT_IN_CATALOG)
+            .unwrap_or_else(|errors| panic!("Invalid built-in gun catalog: {}", errors.join(", ")))
+    )));
+}
+
+/// Set once anything reads the catalog, since guns and generators refer into it from then on.
+static CATALOG_IN_USE: AtomicBool = AtomicBool::new(false);
+
+fn catalog() -> &'static GunCatalog {
+    CATALOG_IN_USE.store(true, Ordering::Relaxed);
+    *CATALOG.read().unwrap()
+}
+
+/// Replaces the built-in gun catalog, keeping it and logging every problem if `json` is invalid.
+///
+/// Only possible before the catalog is first used, so that no gun is left referring to a replaced
+/// catalog and at most one catalog is leaked besides the built-in one.
+#[cfg(target_arch = "wasm32")]
+pub fn load_catalog(json: &str) -> bool {
+    if CATALOG_IN_USE.load(Ordering::Relaxed) {
+        log::error!("Gun catalog can't be replaced once it is in use");
+        return false;
+    }
+
+    match GunCatalog::parse(json) {
+        Ok(catalog) => {
+            log::info!(
+                "Loaded {} gun archetypes and {} modifiers",
+                catalog.archetypes.len(),
+                catalog.modifiers.len()
+            );
+
+            *CATALOG.write().unwrap() = Box::leak(Box::new(catalog));
+            true
+        }
+        Err(errors) => {
+            for error in errors {
+                log::error!("Invalid gun catalog: {}", error);
+            }
+
+            false
+        }
+    }
+}
+
+mod catalog_units {
+    use serde::{Deserialize, Deserializer};
+
+    use crate::graphics::texture;
+    use crate::util::vector::Vec2;
+    use crate::Mat3;
+
+    const TEXTURE_PIXELS: f64 = 32.0;
+
+    #[derive(Deserialize)]
+    struct TileRect {
+        position: Vec2,
+        size: Vec2,
+    }
+
+    pub(super) fn texture<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Mat3, D::Error> {
+        let rect = TileRect::deserialize(deserializer)?;
+        Ok(texture::from_tiles(rect.position, rect.size))
+    }
+
+    pub(super) fn pixels<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec2, D::Error> {
+        Ok(Vec2::deserialize(deserializer)? / TEXTURE_PIXELS)
+    }
 }
 
+mod archetype_name {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::gun::{catalog, GunArchetype};
+
+    pub(super) fn serialize<S: Serializer>(
+        archetype: &&'static GunArchetype,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&archetype.name)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<&'static GunArchetype, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        catalog()
+            .archetypes
+            .iter()
+            .find(|archetype| archetype.name == name)
+            .ok_or_else(|| D::Error::custom(format!("unknown gun archetype {}", name)))
+    }
+}
+
+mod modifier_names {
+    use serde::de::Error;
+    use serde::ser::SerializeSeq;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::gun::{catalog, Modifier};
+
+    pub(super) fn serialize<S: Serializer>(
+        modifiers: &[&'static Modifier],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(modifiers.len()))?;
+        for modifier in modifiers {
+            seq.serialize_element(&modifier.name)?;
+        }
+        seq.end()
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<&'static Modifier>, D::Error> {
+        let names = Vec::<String>::deserialize(deserializer)?;
+        names
+            .into_iter()
+            .map(|name| {
+                catalog()
+                    .modifiers
+                    .iter()
+                    .find(|modifier| modifier.name == name)
+                    .ok_or_else(|| D::Error::custom(format!("unknown gun modifier {}", name)))
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GunSpecGenerator {
-    archetype_counts: FxHashMap<usize, usize>,
-    modifier_counts: FxHashMap<Modifier, usize>,
+    archetype_counts: FxHashMap<String, usize>,
+    modifier_counts: FxHashMap<String, usize>,
 }
 
 impl GunSpecGenerator {
@@ -288,140 +886,135 @@ impl GunSpecGenerator {
         }
     }
 
-    pub fn generate(&mut self, position: Vec2) -> GunSpec {
-        let distance = position.length() + Random::global().next_f64_in(0.0..25.0);
+    pub fn generate(&mut self, random: &mut Random, position: Vec2) -> GunSpec {
+        let distance = position.length() + random.next_f64_in(0.0..25.0);
         let quality = distance / 70.0 + 0.33;
 
-        let archetype = self.generate_archetype();
-        let modifier = self.generate_modifier();
-
-        let mut spec = GunSpec {
-            name: format!("{}{}", modifier.prefix(), archetype.name),
-            damage: archetype.damage,
-            knockback: archetype.knockback,
-            cooldown: archetype.cooldown,
-            penetration: archetype.penetration,
-            accuracy: archetype.accuracy,
-            bullet_count: archetype.bullet_count,
-            ammo: archetype.ammo,
-            archetype,
-        };
-
-        spec.damage += spec.damage * quality;
-        modifier.apply(&mut spec);
+        let catalog = catalog();
+        let archetype = self.generate_archetype(catalog, random);
+        let modifier = self.generate_modifier(catalog, random);
 
-        spec
+        GunSpec::build(archetype.name.clone(), archetype, quality, vec![modifier])
     }
 
-    fn generate_archetype(&mut self) -> &'static GunArchetype {
-        let scores = ARCHETYPES
+    fn generate_archetype(
+        &mut self,
+        catalog: &'static GunCatalog,
+        random: &mut Random,
+    ) -> &'static GunArchetype {
+        let scores = catalog
+            .archetypes
             .iter()
-            .enumerate()
-            .map(|(i, archetype)| {
-                let count = *self.archetype_counts.entry(i).or_insert(0);
+            .map(|archetype| {
+                let count = *self
+                    .archetype_counts
+                    .entry(archetype.name.clone())
+                    .or_insert(0);
                 1.0 / ((count + 1) as f64 * archetype.rarity)
             })
             .collect::<Vec<_>>();
 
-        let archetype_index = Random::global().weighted_index(|| scores.iter().copied());
+        let archetype_index = random.weighted_index(|| scores.iter().copied());
+        let archetype = &catalog.archetypes[archetype_index];
 
-        *self.archetype_counts.entry(archetype_index).or_insert(0) += 1;
-        &ARCHETYPES[archetype_index]
+        *self
+            .archetype_counts
+            .entry(archetype.name.clone())
+            .or_insert(0) += 1;
+        archetype
     }
 
-    fn generate_modifier(&mut self) -> Modifier {
-        let scores = MODIFIERS
+    fn generate_modifier(
+        &mut self,
+        catalog: &'static GunCatalog,
+        random: &mut Random,
+    ) -> &'static Modifier {
+        let scores = catalog
+            .modifiers
             .iter()
             .map(|modifier| {
-                let count = *self.modifier_counts.entry(*modifier).or_insert(0);
-                1.0 / ((count + 1) as f64 * modifier.rarity())
+                let count = *self
+                    .modifier_counts
+                    .entry(modifier.name.clone())
+                    .or_insert(0);
+                1.0 / ((count + 1) as f64 * modifier.rarity)
             })
             .collect::<Vec<_>>();
 
-        let modifier_index = Random::global().weighted_index(|| scores.iter().copied());
-        let modifier = MODIFIERS[modifier_index];
+        let modifier_index = random.weighted_index(|| scores.iter().copied());
+        let modifier = &catalog.modifiers[modifier_index];
 
-        *self.modifier_counts.entry(modifier).or_insert(0) += 1;
+        *self
+            .modifier_counts
+            .entry(modifier.name.clone())
+            .or_insert(0) += 1;
         modifier
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash)]
-enum Modifier {
-    Plain,
-    Brutal,
-    Plentiful,
-    Rapid,
-    Forceful,
-    Unhinged,
-    Wild,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-impl Modifier {
-    pub fn prefix(&self) -> &'static str {
-        match self {
-            Modifier::Plain => "",
-            Modifier::Brutal => "Brutal ",
-            Modifier::Plentiful => "Plentiful ",
-            Modifier::Rapid => "Rapid ",
-            Modifier::Forceful => "Forceful ",
-            Modifier::Unhinged => "Unhinged ",
-            Modifier::Wild => "Wild ",
-        }
+    #[test]
+    fn built_in_catalog_is_valid() {
+        assert!(GunCatalog::parse(BUILT_IN_CATALOG).is_ok());
     }
 
-    pub fn apply(&self, spec: &mut GunSpec) {
-        match self {
-            Modifier::Plain => {}
-            Modifier::Brutal => {
-                spec.damage *= 1.75;
-                spec.cooldown *= 0.75;
-                spec.ammo = spec.ammo * 2 / 3;
-            }
-            Modifier::Plentiful => {
-                spec.ammo = spec.ammo * 3 / 2;
-            }
-            Modifier::Rapid => {
-                spec.knockback *= 0.5;
-                spec.cooldown *= 0.66;
-                spec.accuracy *= 0.75;
-            }
-            Modifier::Forceful => {
-                spec.cooldown *= 1.15;
-                spec.knockback *= 2.5;
-            }
-            Modifier::Unhinged => {
-                spec.damage *= 2.5;
-                spec.knockback *= 4.0;
-                spec.accuracy = (spec.accuracy * 0.25).min(4.0);
-            }
-            Modifier::Wild => {
-                spec.damage *= 0.66;
-                spec.bullet_count *= 2;
-                spec.accuracy *= 0.5;
-            }
-        }
+    #[test]
+    fn invalid_catalog_reports_every_problem() {
+        let json = BUILT_IN_CATALOG
+            .replacen("\"cooldown\": 0.66", "\"cooldown\": 0.0", 1)
+            .replace("\"archetype\": \"Pistol\"", "\"archetype\": \"Crossbow\"");
+
+        let errors = GunCatalog::parse(&json).err().unwrap();
+        assert_eq!(errors.len(), 2);
     }
 
-    pub fn rarity(&self) -> f64 {
-        match self {
-            Modifier::Plain => 0.1,
-            Modifier::Brutal => 1.0,
-            Modifier::Plentiful => 1.0,
-            Modifier::Rapid => 1.0,
-            Modifier::Forceful => 1.5,
-            Modifier::Unhinged => 2.0,
-            Modifier::Wild => 1.5,
+    #[test]
+    fn invalid_modifier_effects_are_reported() {
+        let json = BUILT_IN_CATALOG.replace("\"strength\": 0.4", "\"strength\": 1.5");
+
+        let errors = GunCatalog::parse(&json).err().unwrap();
+        assert_eq!(
+            errors,
+            vec!["modifier Frost must have a positive effect duration and a valid strength"]
+        );
+    }
+
+    #[test]
+    fn upgrades_stack_modifiers_up_to_the_limit() {
+        let mut random = Random::new(42);
+        let mut spec = GunSpec::starter_gun();
+
+        for count in 1..=MAX_MODIFIERS {
+            spec = spec.upgraded(GunUpgrade::Enhance, &mut random).unwrap();
+            assert_eq!(spec.modifiers.len(), count);
         }
+
+        assert!(spec.upgraded(GunUpgrade::Enhance, &mut random).is_none());
+
+        let prefixes = spec
+            .modifiers
+            .iter()
+            .map(|modifier| modifier.prefix.as_str())
+            .collect::<String>();
+        assert_eq!(spec.name(), format!("{}{}", prefixes, spec.base_name));
+
+        let rerolled = spec.upgraded(GunUpgrade::Reroll, &mut random).unwrap();
+        let names = |spec: &GunSpec| {
+            spec.modifiers
+                .iter()
+                .map(|modifier| modifier.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let (old, new) = (names(&spec), names(&rerolled));
+        assert_eq!(old[..2], new[..2]);
+        assert_ne!(old[2], new[2]);
+
+        let refined = spec.upgraded(GunUpgrade::Refine, &mut random).unwrap();
+        assert!(refined.damage() > spec.damage());
+        assert_eq!(refined.name(), spec.name());
     }
 }
-
-const MODIFIERS: &[Modifier] = &[
-    Modifier::Plain,
-    Modifier::Brutal,
-    Modifier::Plentiful,
-    Modifier::Rapid,
-    Modifier::Forceful,
-    Modifier::Unhinged,
-    Modifier::Wild,
-];
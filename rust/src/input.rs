@@ -1,9 +1,139 @@
 use crate::util::vector::Vec2;
 use crate::vec2;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::mem;
 
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InputSource {
+    Keyboard,
+    KeyboardLeft,
+    KeyboardRight,
+    Gamepad,
+    Touch,
+}
+
+impl InputSource {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "keyboard" => Some(InputSource::Keyboard),
+            "keyboardLeft" => Some(InputSource::KeyboardLeft),
+            "keyboardRight" => Some(InputSource::KeyboardRight),
+            "gamepad" => Some(InputSource::Gamepad),
+            "touch" => Some(InputSource::Touch),
+            _ => None,
+        }
+    }
+
+    /// Keys moving up, left, down and right respectively.
+    fn direction_keys(self) -> [&'static [&'static str]; 4] {
+        match self {
+            InputSource::Keyboard => [
+                &["KeyW", "ArrowUp"],
+                &["KeyA", "ArrowLeft"],
+                &["KeyS", "ArrowDown"],
+                &["KeyD", "ArrowRight"],
+            ],
+            InputSource::KeyboardLeft => [&["KeyW"], &["KeyA"], &["KeyS"], &["KeyD"]],
+            InputSource::KeyboardRight => [
+                &["ArrowUp"],
+                &["ArrowLeft"],
+                &["ArrowDown"],
+                &["ArrowRight"],
+            ],
+            InputSource::Gamepad | InputSource::Touch => [&[], &[], &[], &[]],
+        }
+    }
+
+    fn interact_key(self) -> Option<&'static str> {
+        match self {
+            InputSource::Keyboard | InputSource::KeyboardLeft => Some("Space"),
+            InputSource::KeyboardRight => Some("Enter"),
+            InputSource::Gamepad | InputSource::Touch => None,
+        }
+    }
+
+    fn reload_key(self) -> Option<&'static str> {
+        match self {
+            InputSource::Keyboard | InputSource::KeyboardLeft => Some("KeyR"),
+            InputSource::KeyboardRight => Some("ShiftRight"),
+            InputSource::Gamepad | InputSource::Touch => None,
+        }
+    }
+
+    fn dash_key(self) -> Option<&'static str> {
+        match self {
+            InputSource::Keyboard | InputSource::KeyboardLeft => Some("ShiftLeft"),
+            InputSource::KeyboardRight => Some("ControlRight"),
+            InputSource::Gamepad | InputSource::Touch => None,
+        }
+    }
+
+    fn slot_key_prefix(self) -> Option<&'static str> {
+        match self {
+            InputSource::Keyboard | InputSource::KeyboardLeft => Some("Digit"),
+            InputSource::KeyboardRight => Some("Numpad"),
+            InputSource::Gamepad | InputSource::Touch => None,
+        }
+    }
+
+    pub fn is_interact_key(self, key: &str) -> bool {
+        self.interact_key() == Some(key)
+    }
+
+    pub fn is_reload_key(self, key: &str) -> bool {
+        self.reload_key() == Some(key)
+    }
+
+    pub fn is_dash_key(self, key: &str) -> bool {
+        self.dash_key() == Some(key)
+    }
+
+    pub fn parse_slot_key(self, key: &str) -> Option<usize> {
+        let digit: usize = key.strip_prefix(self.slot_key_prefix()?)?.parse().ok()?;
+
+        if digit == 0 {
+            Some(9)
+        } else {
+            Some(digit - 1)
+        }
+    }
+
+    fn accepts_key(self, key: &str) -> bool {
+        self.direction_keys().iter().any(|keys| keys.contains(&key))
+            || self.is_interact_key(key)
+            || self.is_reload_key(key)
+            || self.is_dash_key(key)
+            || self.parse_slot_key(key).is_some()
+    }
+
+    /// Whether aiming is relative to the player rather than a position on the shared screen.
+    pub fn aims_from_player(self) -> bool {
+        self == InputSource::Gamepad
+    }
+
+    pub fn revive_hint(self) -> &'static str {
+        match self {
+            InputSource::Keyboard | InputSource::KeyboardLeft => "Press SPACE to revive",
+            InputSource::KeyboardRight => "Press ENTER to revive",
+            InputSource::Gamepad => "Press A to revive",
+            InputSource::Touch => "Tap joystick to revive",
+        }
+    }
+
+    pub fn purchase_hint(self) -> &'static str {
+        match self {
+            InputSource::Keyboard | InputSource::KeyboardLeft => "Press SPACE to purchase",
+            InputSource::KeyboardRight => "Press ENTER to purchase",
+            InputSource::Gamepad => "Press A to purchase",
+            InputSource::Touch => "Tap joystick to purchase",
+        }
+    }
+}
+
 pub struct Inputs {
+    source: InputSource,
     mouse: Vec2,
     joystick: Vec2,
     mouse_down: bool,
@@ -13,7 +143,12 @@ pub struct Inputs {
 
 impl Inputs {
     pub fn new() -> Self {
+        Self::with_source(InputSource::Keyboard)
+    }
+
+    pub fn with_source(source: InputSource) -> Self {
         Self {
+            source,
             mouse: Vec2::zero(),
             joystick: Vec2::zero(),
             mouse_down: false,
@@ -22,14 +157,23 @@ impl Inputs {
         }
     }
 
+    pub fn source(&self) -> InputSource {
+        self.source
+    }
+
     pub fn joystick_mut(&mut self) -> &mut Vec2 {
         &mut self.joystick
     }
-    
+
     pub fn joystick_tap(&mut self) {
         self.events.push(Event::JoystickTap);
     }
 
+    /// A quick flick of the joystick, dashing on touch screens.
+    pub fn joystick_swipe(&mut self) {
+        self.events.push(Event::JoystickSwipe);
+    }
+
     pub fn mouse(&self) -> Vec2 {
         self.mouse
     }
@@ -46,7 +190,15 @@ impl Inputs {
         self.down_keys.contains(key)
     }
 
+    fn is_any_key_down(&self, keys: &[&str]) -> bool {
+        keys.iter().any(|key| self.is_key_down(key))
+    }
+
     pub fn key_down(&mut self, key: String) {
+        if !self.source.accepts_key(&key) {
+            return;
+        }
+
         if !self.down_keys.contains(&key) {
             self.down_keys.insert(key.clone());
         }
@@ -55,6 +207,10 @@ impl Inputs {
     }
 
     pub fn key_up(&mut self, key: String) {
+        if !self.source.accepts_key(&key) {
+            return;
+        }
+
         self.down_keys.remove(&key);
 
         self.events.push(Event::KeyUp { key });
@@ -77,20 +233,21 @@ impl Inputs {
 
     pub fn movement_direction(&self) -> Vec2 {
         let mut direction = self.joystick;
+        let [up, left, down, right] = self.source.direction_keys();
 
-        if self.is_key_down("KeyW") || self.is_key_down("ArrowUp") {
+        if self.is_any_key_down(up) {
             direction += vec2(0.0, 1.0);
         }
 
-        if self.is_key_down("KeyA") || self.is_key_down("ArrowLeft") {
+        if self.is_any_key_down(left) {
             direction += vec2(-1.0, 0.0);
         }
 
-        if self.is_key_down("KeyS") || self.is_key_down("ArrowDown") {
+        if self.is_any_key_down(down) {
             direction += vec2(0.0, -1.0);
         }
 
-        if self.is_key_down("KeyD") || self.is_key_down("ArrowRight") {
+        if self.is_any_key_down(right) {
             direction += vec2(1.0, 0.0);
         }
 
@@ -102,10 +259,17 @@ impl Inputs {
     }
 }
 
+impl Default for Inputs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub enum Event {
     KeyUp { key: String },
     KeyDown { key: String },
     MouseDown { position: Vec2 },
     MouseUp { position: Vec2 },
     JoystickTap,
+    JoystickSwipe,
 }
@@ -1,20 +1,29 @@
+use std::rc::Rc;
+
 use crate::audio::{play_sound, sound};
 use serde::{Serialize, Serializer};
-use specs::{Entity, World};
+use specs::{Entity, Join, World, WorldExt};
 
 use crate::components::bounds::Bounds;
+use crate::components::health::Health;
 use crate::components::player::Player;
 use crate::ecs::WorldExtensions;
-use crate::game::IsMobile;
-use crate::gun::GunSpec;
+use crate::game::ControlledPlayers;
+use crate::gun::{Gun, GunSpec, GunUpgrade};
+use crate::input::InputSource;
 use crate::map::chest::Chest;
 use crate::map::{Map, Tile};
+use crate::perk::{self, Perk};
 use crate::util::coord::Coord;
 use crate::util::rect::Rect;
 use crate::util::vector::Vec2;
 use crate::vec2;
 
 const INTERACT_RANGE: f64 = 0.33;
+const AMMO_REFILL_COST: f64 = 250.0;
+const ENHANCE_COST: f64 = 1200.0;
+const REFINE_COST: f64 = 800.0;
+const REROLL_COST: f64 = 400.0;
 
 pub struct Interaction {
     heading: UiText,
@@ -23,32 +32,41 @@ pub struct Interaction {
 }
 
 impl Interaction {
-    pub fn attempt_interact(world: &World) {
-        let interaction = match Self::current(world) {
+    pub fn attempt_interact(world: &World, player: Entity) {
+        let interaction = match Self::current(world, player) {
             Some(interaction) => interaction,
             None => return,
         };
 
-        interaction.interaction_type.perform(world);
+        interaction.interaction_type.perform(world, player);
     }
 
-    pub fn current(world: &World) -> Option<Interaction> {
-        let bounds = world.controlled_player_read::<Bounds>()?.0;
+    pub fn current(world: &World, player: Entity) -> Option<Interaction> {
+        if world.unwrap_read::<Player>(player).is_down() {
+            return None;
+        }
+
+        let bounds = world.unwrap_read::<Bounds>(player).0;
 
         let search = bounds.expand(INTERACT_RANGE);
 
         let mut current = None;
         let mut current_distance = INTERACT_RANGE;
 
-        Self::current_barrier(world, search, &mut current, &mut current_distance);
-        Self::current_chest(world, search, &mut current, &mut current_distance);
-        Self::current_pickup(world, search, &mut current, &mut current_distance);
+        Self::current_barrier(world, player, search, &mut current, &mut current_distance);
+        Self::current_revive(world, player, search, &mut current, &mut current_distance);
+        Self::current_chest(world, player, search, &mut current, &mut current_distance);
+        Self::current_pickup(world, player, search, &mut current, &mut current_distance);
+        Self::current_ammo_station(world, player, search, &mut current, &mut current_distance);
+        Self::current_perk_station(world, player, search, &mut current, &mut current_distance);
+        Self::current_upgrade_bench(world, player, search, &mut current, &mut current_distance);
 
         current
     }
 
     fn current_barrier(
         world: &World,
+        player: Entity,
         search: Rect,
         current: &mut Option<Interaction>,
         current_distance: &mut f64,
@@ -70,7 +88,7 @@ impl Interaction {
             *current_distance = distance;
             *current = Some(Interaction {
                 heading: UiText::of(format!("Remove barrier ${}", cost), TextColor::white()),
-                caption: Self::cost_caption(world, cost),
+                caption: Self::cost_caption(world, player, cost),
                 interaction_type: InteractionType::Barrier {
                     cost,
                     position: coord,
@@ -79,8 +97,57 @@ impl Interaction {
         }
     }
 
+    fn current_revive(
+        world: &World,
+        player: Entity,
+        search: Rect,
+        current: &mut Option<Interaction>,
+        current_distance: &mut f64,
+    ) {
+        let entities = world.entities();
+        let bounds = world.read_storage::<Bounds>();
+        let players = world.read_storage::<Player>();
+
+        for (target, target_bounds, target_player) in (&entities, &bounds, &players).join() {
+            if !target_player.is_down() {
+                continue;
+            }
+
+            let distance = Rect::euclidean_distance(target_bounds.0, search);
+
+            if distance >= *current_distance {
+                continue;
+            }
+
+            let (heading, caption) = if target_player.is_being_revived() {
+                (
+                    UiText::of("Reviving player", TextColor::white()),
+                    UiText::of("Stay close", TextColor::green()),
+                )
+            } else {
+                let source = world
+                    .fetch::<ControlledPlayers>()
+                    .source_of(player)
+                    .unwrap_or(InputSource::Keyboard);
+
+                (
+                    UiText::of("Revive player", TextColor::white()),
+                    UiText::of(source.revive_hint(), TextColor::green()),
+                )
+            };
+
+            *current_distance = distance;
+            *current = Some(Interaction {
+                heading,
+                caption,
+                interaction_type: InteractionType::Revive { target },
+            });
+        }
+    }
+
     fn current_chest(
         world: &World,
+        player: Entity,
         search: Rect,
         current: &mut Option<Interaction>,
         current_distance: &mut f64,
@@ -103,7 +170,7 @@ impl Interaction {
             *current_distance = distance;
             *current = Some(Interaction {
                 heading: UiText::of(format!("Open chest ${}", cost), TextColor::white()),
-                caption: Self::cost_caption(world, cost),
+                caption: Self::cost_caption(world, player, cost),
                 interaction_type: InteractionType::Chest {
                     cost,
                     position: chest.position(),
@@ -114,17 +181,13 @@ impl Interaction {
 
     fn current_pickup(
         world: &World,
+        player: Entity,
         search: Rect,
         current: &mut Option<Interaction>,
         current_distance: &mut f64,
     ) {
         let map = world.fetch::<Map>();
 
-        let player = match world.controlled_player() {
-            Some(player) => player,
-            None => return,
-        };
-
         for chest in map.chests_in(search) {
             if !chest.can_pickup(player) {
                 continue;
@@ -149,6 +212,188 @@ impl Interaction {
         }
     }
 
+    fn current_ammo_station(
+        world: &World,
+        player: Entity,
+        search: Rect,
+        current: &mut Option<Interaction>,
+        current_distance: &mut f64,
+    ) {
+        let map = world.fetch::<Map>();
+
+        for ammo_station in map.ammo_stations_in(search) {
+            let distance = Rect::euclidean_distance(ammo_station.bounds(), search);
+
+            if distance >= *current_distance {
+                continue;
+            }
+
+            let player_component = world.unwrap_read::<Player>(player);
+            let gun = player_component.selected_gun();
+
+            let (heading, caption, cost) = if gun.missing_ammo() == 0 {
+                (
+                    UiText::of("Ammo full", TextColor::white()),
+                    UiText::new(),
+                    0,
+                )
+            } else {
+                let cost = Self::ammo_refill_cost(gun);
+                let heading = format!("Refill {} ammo ${}", gun.spec().name(), cost);
+
+                (
+                    UiText::of(heading, TextColor::white()),
+                    Self::cost_caption(world, player, cost),
+                    cost,
+                )
+            };
+
+            *current_distance = distance;
+            *current = Some(Interaction {
+                heading,
+                caption,
+                interaction_type: InteractionType::AmmoRefill {
+                    cost,
+                    position: ammo_station.position(),
+                },
+            });
+        }
+    }
+
+    fn current_perk_station(
+        world: &World,
+        player: Entity,
+        search: Rect,
+        current: &mut Option<Interaction>,
+        current_distance: &mut f64,
+    ) {
+        let map = world.fetch::<Map>();
+
+        for perk_station in map.perk_stations_in(search) {
+            let distance = Rect::euclidean_distance(perk_station.bounds(), search);
+
+            if distance >= *current_distance {
+                continue;
+            }
+
+            let perk = perk_station.perk();
+            let player_component = world.unwrap_read::<Player>(player);
+
+            let (heading, caption, cost) = if player_component.has_perk(perk) {
+                (
+                    UiText::of(format!("{} owned", perk.name()), TextColor::white()),
+                    UiText::new(),
+                    0,
+                )
+            } else {
+                let cost = Perk::cost(player_component.perks().len());
+                let heading = format!("Buy {} perk ${}", perk.name(), cost);
+
+                (
+                    UiText::of(heading, TextColor::white()),
+                    Self::cost_caption(world, player, cost),
+                    cost,
+                )
+            };
+
+            *current_distance = distance;
+            *current = Some(Interaction {
+                heading,
+                caption,
+                interaction_type: InteractionType::Perk {
+                    cost,
+                    perk,
+                    position: perk_station.position(),
+                },
+            });
+        }
+    }
+
+    fn current_upgrade_bench(
+        world: &World,
+        player: Entity,
+        search: Rect,
+        current: &mut Option<Interaction>,
+        current_distance: &mut f64,
+    ) {
+        let map = world.fetch::<Map>();
+
+        for upgrade_bench in map.upgrade_benches_in(search) {
+            let distance = Rect::euclidean_distance(upgrade_bench.bounds(), search);
+
+            if distance >= *current_distance {
+                continue;
+            }
+
+            let upgrade = upgrade_bench.upgrade();
+            let player_component = world.unwrap_read::<Player>(player);
+            let gun = player_component.selected_gun();
+
+            let (heading, caption, cost, spec) = match upgrade_bench.upgraded(gun.spec()) {
+                None => {
+                    let heading = format!(
+                        "Can't {} {}",
+                        upgrade.name().to_lowercase(),
+                        gun.spec().name()
+                    );
+
+                    (
+                        UiText::of(heading, TextColor::white()),
+                        UiText::new(),
+                        0,
+                        None,
+                    )
+                }
+                Some(spec) => {
+                    let cost = Self::upgrade_cost(upgrade, gun);
+                    let heading = format!("{} {} ${}", upgrade.name(), gun.spec().name(), cost);
+
+                    let mut caption = UiText::new();
+                    Self::pickup_caption_similar(&mut caption, gun.spec(), &spec);
+                    caption.append(Self::cost_caption(world, player, cost));
+
+                    (
+                        UiText::of(heading, TextColor::white()),
+                        caption,
+                        cost,
+                        Some(Rc::new(spec)),
+                    )
+                }
+            };
+
+            *current_distance = distance;
+            *current = Some(Interaction {
+                heading,
+                caption,
+                interaction_type: InteractionType::Upgrade {
+                    cost,
+                    spec,
+                    position: upgrade_bench.position(),
+                },
+            });
+        }
+    }
+
+    /// Scales with how good the gun already is, like refilling its ammo.
+    fn upgrade_cost(upgrade: GunUpgrade, gun: &Gun) -> usize {
+        let base = match upgrade {
+            GunUpgrade::Enhance => ENHANCE_COST,
+            GunUpgrade::Refine => REFINE_COST,
+            GunUpgrade::Reroll => REROLL_COST,
+        };
+
+        let cost = base * (1.0 + gun.spec().quality());
+        (cost / 10.0).ceil() as usize * 10
+    }
+
+    /// Scales with the share of the gun's ammo that was spent and how strong the gun is.
+    fn ammo_refill_cost(gun: &Gun) -> usize {
+        let spent = gun.missing_ammo() as f64 / gun.spec().ammo() as f64;
+        let cost = AMMO_REFILL_COST * spent * (1.0 + gun.spec().quality());
+
+        ((cost / 10.0).ceil() as usize * 10).max(10)
+    }
+
     fn pickup_caption(world: &World, player: Entity, new: &GunSpec) -> UiText {
         let player = world.unwrap_read::<Player>(player);
 
@@ -171,6 +416,13 @@ impl Interaction {
         Self::pickup_caption_percent(caption, "ACC", old.accuracy(), new.accuracy());
         Self::pickup_caption_percent(caption, "KB", old.knockback(), new.knockback());
         Self::pickup_caption_absolute(caption, "AMMO", old.ammo(), new.ammo());
+        Self::pickup_caption_absolute(caption, "MAG", old.magazine_size(), new.magazine_size());
+        Self::pickup_caption_percent(
+            caption,
+            "RELOAD",
+            1.0 / old.reload_time(),
+            1.0 / new.reload_time(),
+        );
         Self::pickup_caption_absolute(caption, "BULLETS", old.bullet_count(), new.bullet_count());
     }
 
@@ -264,41 +516,57 @@ impl Interaction {
         &self.caption
     }
 
-    fn cost_caption(world: &World, cost: usize) -> UiText {
-        let credits = Self::controlled_player_credits(world).unwrap_or(0);
-        let is_mobile = world.fetch::<IsMobile>().0;
+    fn cost_caption(world: &World, player: Entity, cost: usize) -> UiText {
+        let credits = world.unwrap_read::<Player>(player).credits();
 
         if credits < cost {
-            UiText::of("Insufficient funds", TextColor::red())
-        } else if is_mobile {
-            UiText::of("Tap joystick to purchase", TextColor::green())
-        } else {
-            UiText::of("Press SPACE to purchase", TextColor::green())
+            return UiText::of("Insufficient funds", TextColor::red());
         }
-    }
 
-    fn controlled_player_credits(world: &World) -> Option<usize> {
-        world
-            .controlled_player_read::<Player>()
-            .map(|player| player.credits())
+        let source = world
+            .fetch::<ControlledPlayers>()
+            .source_of(player)
+            .unwrap_or(InputSource::Keyboard);
+
+        UiText::of(source.purchase_hint(), TextColor::green())
     }
 }
 
 enum InteractionType {
-    Barrier { position: Coord, cost: usize },
-    Chest { position: Vec2, cost: usize },
-    Pickup { position: Vec2 },
+    Barrier {
+        position: Coord,
+        cost: usize,
+    },
+    Chest {
+        position: Vec2,
+        cost: usize,
+    },
+    Pickup {
+        position: Vec2,
+    },
+    AmmoRefill {
+        position: Vec2,
+        cost: usize,
+    },
+    Perk {
+        position: Vec2,
+        perk: Perk,
+        cost: usize,
+    },
+    Revive {
+        target: Entity,
+    },
+    Upgrade {
+        position: Vec2,
+        spec: Option<Rc<GunSpec>>,
+        cost: usize,
+    },
 }
 
 impl InteractionType {
-    fn perform(&self, world: &World) {
+    fn perform(&self, world: &World, player: Entity) {
         let mut map = world.fetch_mut::<Map>();
 
-        let player = match world.controlled_player() {
-            Some(player) => player,
-            None => return,
-        };
-
         match *self {
             InteractionType::Barrier { position, cost } => {
                 if !self.consume_credits(world, player, cost) {
@@ -331,6 +599,77 @@ impl InteractionType {
                     log::warn!("Attempted to pickup from removed chest at {:?}", position);
                 }
             }
+            InteractionType::AmmoRefill { position, cost } => {
+                let gun_is_full = world
+                    .unwrap_read::<Player>(player)
+                    .selected_gun()
+                    .missing_ammo()
+                    == 0;
+
+                if gun_is_full {
+                    return;
+                }
+
+                if !self.consume_credits(world, player, cost) {
+                    return;
+                }
+
+                log::debug!("Refilled ammo at {:?}", position);
+                play_sound(sound::purchase());
+                world
+                    .unwrap_write::<Player>(player)
+                    .selected_gun_mut()
+                    .refill();
+            }
+            InteractionType::Perk {
+                position,
+                perk,
+                cost,
+            } => {
+                if world.unwrap_read::<Player>(player).has_perk(perk) {
+                    return;
+                }
+
+                if !self.consume_credits(world, player, cost) {
+                    return;
+                }
+
+                log::debug!("Bought {} perk at {:?}", perk.name(), position);
+                play_sound(sound::purchase());
+                world.unwrap_write::<Player>(player).add_perk(perk);
+
+                if perk == Perk::Vitality {
+                    world
+                        .unwrap_write::<Health>(player)
+                        .raise_maximum(perk::VITALITY_HEALTH);
+                }
+            }
+            InteractionType::Revive { target } => {
+                if world.unwrap_write::<Player>(target).start_revive() {
+                    log::debug!("Started reviving {:?}", target);
+                }
+            }
+            InteractionType::Upgrade {
+                position,
+                ref spec,
+                cost,
+            } => {
+                let spec = match spec {
+                    Some(spec) => spec.clone(),
+                    None => return,
+                };
+
+                if !self.consume_credits(world, player, cost) {
+                    return;
+                }
+
+                log::debug!("Upgraded gun to {} at {:?}", spec.name(), position);
+                play_sound(sound::purchase());
+                world
+                    .unwrap_write::<Player>(player)
+                    .selected_gun_mut()
+                    .upgrade(spec);
+            }
         }
     }
 
@@ -374,6 +713,10 @@ impl UiText {
     pub fn push(&mut self, text: impl Into<String>, color: TextColor) {
         self.segments.push((text.into(), color));
     }
+
+    pub fn append(&mut self, other: UiText) {
+        self.segments.extend(other.segments);
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -10,33 +10,47 @@ extern crate core;
 
 use std::panic;
 
+#[cfg(target_arch = "wasm32")]
 use js_sys::Function;
 use log::LevelFilter;
+#[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
-use crate::game::Game;
-use crate::input::Inputs;
+#[cfg(target_arch = "wasm32")]
+use crate::game::FIXED_DELTA;
+pub use crate::game::Game;
+pub use crate::input::{InputSource, Inputs};
+pub use crate::map::snapshot::MapSnapshot;
+#[cfg(target_arch = "wasm32")]
+use crate::replay::{InputEvent, Recording};
+pub use crate::util::coord::{coord, Coord};
 use crate::util::mat3::Mat3;
-use crate::util::vector::vec2;
+pub use crate::util::vector::{vec2, Vec2};
 
 mod audio;
 mod camera;
 mod components;
 mod ecs;
 mod entities;
+mod flow_field;
 mod game;
 mod graphics;
 mod gun;
 mod input;
 mod interaction;
+pub mod lockstep;
 mod logging;
 mod map;
+mod perk;
+mod platform;
 mod progression;
+pub mod replay;
+mod save;
 mod util;
 mod weapon_hud;
 mod weapon_positioning;
 
-#[wasm_bindgen(start)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen(start))]
 pub fn start() {
     log::set_logger(logging::LOGGER).unwrap();
     log::set_max_level(LevelFilter::Trace);
@@ -48,53 +62,155 @@ pub fn start() {
     }));
 }
 
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 pub struct Backend {
     game: Game,
+    recording: Recording,
 }
 
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 impl Backend {
     #[wasm_bindgen(constructor)]
     pub fn new(seed: u32, is_mobile: bool) -> Self {
         Self {
             game: Game::new(seed, is_mobile),
+            recording: Recording::new(seed, is_mobile),
         }
     }
 
+    /// Replaces the gun archetypes and modifiers with those described by `json` before any game is
+    /// created, returning false and keeping the built-in ones if it is invalid or too late.
+    #[wasm_bindgen(js_name = loadGunCatalog)]
+    pub fn load_gun_catalog(json: &str) -> bool {
+        gun::load_catalog(json)
+    }
+
     #[wasm_bindgen(js_name = keyDown)]
     pub fn key_down(&mut self, key: String) {
-        self.game.inputs().key_down(key);
+        self.dispatch(InputEvent::KeyDown { player: None, key });
     }
 
     #[wasm_bindgen(js_name = keyUp)]
     pub fn key_up(&mut self, key: String) {
-        self.game.inputs().key_up(key);
+        self.dispatch(InputEvent::KeyUp { player: None, key });
     }
 
     #[wasm_bindgen(js_name = updateJoystick)]
     pub fn update_joystick(&mut self, x: f64, y: f64) {
-        *self.game.inputs().joystick_mut() = vec2(x, y);
+        self.update_player_joystick(0, x, y);
     }
-    
+
     #[wasm_bindgen(js_name = joystickTap)]
     pub fn joystick_tap(&mut self) {
-        self.game.inputs().joystick_tap();
+        self.player_joystick_tap(0);
+    }
+
+    #[wasm_bindgen(js_name = joystickSwipe)]
+    pub fn joystick_swipe(&mut self) {
+        self.player_joystick_swipe(0);
     }
 
     #[wasm_bindgen(js_name = updateMouse)]
     pub fn update_mouse(&mut self, x: f64, y: f64) {
-        *self.game.inputs().mouse_mut() = vec2(x, y);
+        self.update_player_mouse(0, x, y);
     }
 
     #[wasm_bindgen(js_name = mouseDown)]
     pub fn mouse_down(&mut self, x: f64, y: f64) {
-        self.game.inputs().mouse_down(vec2(x, y));
+        self.player_mouse_down(0, x, y);
     }
 
     #[wasm_bindgen(js_name = mouseUp)]
     pub fn mouse_up(&mut self, x: f64, y: f64) {
-        self.game.inputs().mouse_up(vec2(x, y));
+        self.player_mouse_up(0, x, y);
+    }
+
+    #[wasm_bindgen(js_name = updatePlayerJoystick)]
+    pub fn update_player_joystick(&mut self, player: usize, x: f64, y: f64) {
+        self.dispatch(InputEvent::UpdateJoystick { player, x, y });
+    }
+
+    #[wasm_bindgen(js_name = playerJoystickTap)]
+    pub fn player_joystick_tap(&mut self, player: usize) {
+        self.dispatch(InputEvent::JoystickTap { player });
+    }
+
+    #[wasm_bindgen(js_name = playerJoystickSwipe)]
+    pub fn player_joystick_swipe(&mut self, player: usize) {
+        self.dispatch(InputEvent::JoystickSwipe { player });
+    }
+
+    #[wasm_bindgen(js_name = updatePlayerMouse)]
+    pub fn update_player_mouse(&mut self, player: usize, x: f64, y: f64) {
+        self.dispatch(InputEvent::UpdateMouse { player, x, y });
+    }
+
+    #[wasm_bindgen(js_name = playerMouseDown)]
+    pub fn player_mouse_down(&mut self, player: usize, x: f64, y: f64) {
+        self.dispatch(InputEvent::MouseDown { player, x, y });
+    }
+
+    #[wasm_bindgen(js_name = playerMouseUp)]
+    pub fn player_mouse_up(&mut self, player: usize, x: f64, y: f64) {
+        self.dispatch(InputEvent::MouseUp { player, x, y });
+    }
+
+    /// Adds a local player using `keyboard`, `keyboardLeft`, `keyboardRight`, `gamepad` or
+    /// `touch` input, returning its index or -1 if it could not be added.
+    #[wasm_bindgen(js_name = addPlayer)]
+    pub fn add_player(&mut self, source: &str) -> i32 {
+        let source = match InputSource::parse(source) {
+            Some(source) => source,
+            None => {
+                log::error!("Unknown input source {}", source);
+                return -1;
+            }
+        };
+
+        let before = self.game.player_count();
+        self.dispatch(InputEvent::AddPlayer { source });
+
+        if self.game.player_count() > before {
+            before as i32
+        } else {
+            -1
+        }
+    }
+
+    #[wasm_bindgen(js_name = playerCount)]
+    pub fn player_count(&self) -> usize {
+        self.game.player_count()
+    }
+
+    #[wasm_bindgen(js_name = isPlayerAlive)]
+    pub fn is_player_alive(&self, player: usize) -> bool {
+        self.game.is_player_alive(player)
+    }
+
+    #[wasm_bindgen(js_name = saveState)]
+    pub fn save_state(&self) -> String {
+        self.game.save_state()
+    }
+
+    #[wasm_bindgen(js_name = loadState)]
+    pub fn load_state(&mut self, state: String) -> bool {
+        match self.game.load_state(&state) {
+            Ok(()) => {
+                self.recording.record(InputEvent::LoadState { state });
+                true
+            }
+            Err(error) => {
+                log::error!("Failed to load state: {}", error);
+                false
+            }
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn recording(&self) -> String {
+        self.recording.to_json()
     }
 
     #[wasm_bindgen(js_name = entityCount)]
@@ -107,34 +223,120 @@ impl Backend {
         self.game.round()
     }
 
+    #[wasm_bindgen(js_name = bossHealth)]
+    pub fn boss_health(&self) -> Option<f64> {
+        self.game.boss_health()
+    }
+
     #[wasm_bindgen]
     pub fn credits(&self) -> usize {
-        self.game.credits().unwrap_or(0)
+        self.player_credits(0)
     }
 
     #[wasm_bindgen(js_name = interactionLine)]
     pub fn interaction_heading(&self) -> String {
-        self.game.interaction_heading()
+        self.player_interaction_heading(0)
     }
 
     #[wasm_bindgen(js_name = interactionCaption)]
     pub fn interaction_caption(&self) -> String {
-        self.game.interaction_caption()
+        self.player_interaction_caption(0)
     }
 
     #[wasm_bindgen(js_name = currentAmmo)]
     pub fn current_ammo(&self) -> usize {
-        self.game.current_ammo().unwrap_or(0)
+        self.player_current_ammo(0)
     }
 
     #[wasm_bindgen(js_name = maxAmmo)]
     pub fn max_ammo(&self) -> usize {
-        self.game.max_ammo().unwrap_or(0)
+        self.player_max_ammo(0)
+    }
+
+    #[wasm_bindgen(js_name = reserveAmmo)]
+    pub fn reserve_ammo(&self) -> usize {
+        self.player_reserve_ammo(0)
+    }
+
+    #[wasm_bindgen(js_name = reloadProgress)]
+    pub fn reload_progress(&self) -> Option<f64> {
+        self.player_reload_progress(0)
+    }
+
+    #[wasm_bindgen(js_name = dashCooldown)]
+    pub fn dash_cooldown(&self) -> f64 {
+        self.player_dash_cooldown(0)
+    }
+
+    #[wasm_bindgen(js_name = bleedOut)]
+    pub fn bleed_out(&self) -> Option<f64> {
+        self.player_bleed_out(0)
+    }
+
+    #[wasm_bindgen(js_name = ownedPerks)]
+    pub fn owned_perks(&self) -> String {
+        self.player_owned_perks(0)
+    }
+
+    #[wasm_bindgen(js_name = playerCredits)]
+    pub fn player_credits(&self, player: usize) -> usize {
+        self.game.credits(player).unwrap_or(0)
+    }
+
+    #[wasm_bindgen(js_name = playerInteractionLine)]
+    pub fn player_interaction_heading(&self, player: usize) -> String {
+        self.game.interaction_heading(player)
+    }
+
+    #[wasm_bindgen(js_name = playerInteractionCaption)]
+    pub fn player_interaction_caption(&self, player: usize) -> String {
+        self.game.interaction_caption(player)
+    }
+
+    #[wasm_bindgen(js_name = playerCurrentAmmo)]
+    pub fn player_current_ammo(&self, player: usize) -> usize {
+        self.game.current_ammo(player).unwrap_or(0)
+    }
+
+    #[wasm_bindgen(js_name = playerMaxAmmo)]
+    pub fn player_max_ammo(&self, player: usize) -> usize {
+        self.game.max_ammo(player).unwrap_or(0)
+    }
+
+    #[wasm_bindgen(js_name = playerReserveAmmo)]
+    pub fn player_reserve_ammo(&self, player: usize) -> usize {
+        self.game.reserve_ammo(player).unwrap_or(0)
+    }
+
+    /// Progress of the selected gun's reload from 0 to 1, or undefined if it is not reloading.
+    #[wasm_bindgen(js_name = playerReloadProgress)]
+    pub fn player_reload_progress(&self, player: usize) -> Option<f64> {
+        self.game.reload_progress(player)
+    }
+
+    /// Remaining dash cooldown from 1 right after dashing down to 0 once it's ready again.
+    #[wasm_bindgen(js_name = playerDashCooldown)]
+    pub fn player_dash_cooldown(&self, player: usize) -> f64 {
+        self.game.dash_cooldown(player).unwrap_or(0.0)
+    }
+
+    /// Remaining bleed-out time of a downed player from 1 down to 0, or undefined if not downed.
+    #[wasm_bindgen(js_name = playerBleedOut)]
+    pub fn player_bleed_out(&self, player: usize) -> Option<f64> {
+        self.game.bleed_out(player)
+    }
+
+    /// JSON array of the perks the player has bought, such as `["vitality","swiftness"]`.
+    #[wasm_bindgen(js_name = playerOwnedPerks)]
+    pub fn player_owned_perks(&self, player: usize) -> String {
+        self.game.owned_perks(player)
     }
 
     #[wasm_bindgen]
     pub fn tick(&mut self, delta: f64) {
-        self.game.tick(delta);
+        for _ in 0..self.game.accumulate(delta) {
+            self.dispatch(InputEvent::Tick { delta: FIXED_DELTA });
+        }
     }
 
     #[wasm_bindgen]
@@ -156,8 +358,9 @@ impl Backend {
     pub fn is_over(&self) -> bool {
         self.game.is_over()
     }
-}
 
-fn current_time() -> f64 {
-    web_sys::window().unwrap().performance().unwrap().now()
+    fn dispatch(&mut self, event: InputEvent) {
+        event.apply(&mut self.game);
+        self.recording.record(event);
+    }
 }
@@ -0,0 +1,361 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::mem;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Game, FIXED_DELTA, MAX_LOCAL_PLAYERS};
+use crate::input::InputSource;
+use crate::replay::InputEvent;
+
+const INPUT_DELAY: u64 = 3;
+const CHECKSUM_INTERVAL: u64 = 60;
+const MAX_CATCH_UP_TICKS: f64 = 8.0;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Message {
+    Frame {
+        peer: usize,
+        tick: u64,
+        events: Vec<InputEvent>,
+    },
+    Checksum {
+        peer: usize,
+        tick: u64,
+        hash: u64,
+    },
+}
+
+/// A reliable, ordered channel broadcasting messages to every other peer in the session.
+pub trait Transport {
+    fn send(&mut self, message: Message);
+
+    fn receive(&mut self) -> Option<Message>;
+}
+
+/// Runs a `Game` in fixed steps, only advancing a tick once every peer's inputs for it are known.
+///
+/// Peer `n` controls local player `n`. Local inputs are scheduled `INPUT_DELAY` ticks ahead so
+/// that they can reach the other peers before they are needed.
+pub struct Lockstep<T: Transport> {
+    game: Game,
+    transport: T,
+    local_peer: usize,
+    peer_count: usize,
+    tick: u64,
+    next_frame: u64,
+    accumulator: f64,
+    pending: Vec<InputEvent>,
+    frames: BTreeMap<u64, Vec<Option<Vec<InputEvent>>>>,
+    checksums: BTreeMap<u64, u64>,
+    remote_checksums: Vec<(usize, u64, u64)>,
+    desynced_at: Option<u64>,
+}
+
+impl<T: Transport> Lockstep<T> {
+    pub fn new(seed: u32, sources: &[InputSource], local_peer: usize, transport: T) -> Self {
+        assert!(!sources.is_empty() && sources.len() <= MAX_LOCAL_PLAYERS);
+        assert!(local_peer < sources.len());
+
+        let mut game = Game::new(seed, sources[0] == InputSource::Touch);
+        for source in &sources[1..] {
+            game.add_player(*source);
+        }
+
+        let peer_count = sources.len();
+
+        let frames = (0..INPUT_DELAY)
+            .map(|tick| (tick, vec![Some(Vec::new()); peer_count]))
+            .collect();
+
+        Self {
+            game,
+            transport,
+            local_peer,
+            peer_count,
+            tick: 0,
+            next_frame: INPUT_DELAY,
+            accumulator: 0.0,
+            pending: Vec::new(),
+            frames,
+            checksums: BTreeMap::new(),
+            remote_checksums: Vec::new(),
+            desynced_at: None,
+        }
+    }
+
+    pub fn record(&mut self, event: InputEvent) {
+        if event.is_player_input() {
+            self.pending.push(event);
+        } else {
+            log::warn!("Ignored non-input event in lockstep session");
+        }
+    }
+
+    /// Advances by as many fixed ticks as `elapsed` allows, returning how many were simulated.
+    pub fn advance(&mut self, elapsed: f64) -> usize {
+        self.receive_messages();
+
+        self.accumulator = (self.accumulator + elapsed).min(FIXED_DELTA * MAX_CATCH_UP_TICKS);
+
+        let mut ticks = 0;
+
+        while self.accumulator >= FIXED_DELTA {
+            self.send_local_frames();
+
+            if !self.step() {
+                break;
+            }
+
+            self.accumulator -= FIXED_DELTA;
+            ticks += 1;
+        }
+
+        ticks
+    }
+
+    fn send_local_frames(&mut self) {
+        while self.next_frame <= self.tick + INPUT_DELAY {
+            let tick = self.next_frame;
+            let local_peer = self.local_peer;
+            let events = mem::take(&mut self.pending);
+
+            self.frame_mut(tick)[local_peer] = Some(events.clone());
+            self.transport.send(Message::Frame {
+                peer: local_peer,
+                tick,
+                events,
+            });
+
+            self.next_frame += 1;
+        }
+    }
+
+    fn step(&mut self) -> bool {
+        let ready = match self.frames.get(&self.tick) {
+            Some(frame) => frame.iter().all(Option::is_some),
+            None => false,
+        };
+
+        if !ready {
+            return false;
+        }
+
+        let frame = self.frames.remove(&self.tick).unwrap();
+
+        for (peer, events) in frame.into_iter().enumerate() {
+            for event in events.unwrap() {
+                event.for_player(peer).apply(&mut self.game);
+            }
+        }
+
+        self.game.tick(FIXED_DELTA);
+        self.tick += 1;
+
+        if self.tick % CHECKSUM_INTERVAL == 0 {
+            self.send_checksum();
+        }
+
+        true
+    }
+
+    fn send_checksum(&mut self) {
+        let hash = self.game.state_hash();
+
+        self.checksums.insert(self.tick, hash);
+        self.transport.send(Message::Checksum {
+            peer: self.local_peer,
+            tick: self.tick,
+            hash,
+        });
+
+        let oldest = self.tick.saturating_sub(CHECKSUM_INTERVAL * 2);
+        self.checksums = self.checksums.split_off(&oldest);
+
+        self.compare_checksums();
+    }
+
+    fn receive_messages(&mut self) {
+        while let Some(message) = self.transport.receive() {
+            match message {
+                Message::Frame { peer, tick, events } => {
+                    if peer >= self.peer_count || tick < self.tick {
+                        log::warn!("Discarded frame for tick {} from peer {}", tick, peer);
+                        continue;
+                    }
+
+                    self.frame_mut(tick)[peer] = Some(events);
+                }
+                Message::Checksum { peer, tick, hash } => {
+                    self.remote_checksums.push((peer, tick, hash));
+                }
+            }
+        }
+
+        self.compare_checksums();
+    }
+
+    fn compare_checksums(&mut self) {
+        let checksums = &self.checksums;
+        let desynced_at = &mut self.desynced_at;
+        let current_tick = self.tick;
+
+        self.remote_checksums.retain(|(peer, tick, hash)| {
+            if *tick > current_tick {
+                return true;
+            }
+
+            match checksums.get(tick) {
+                Some(local) if local != hash => {
+                    if desynced_at.is_none() {
+                        log::error!("Desynced from peer {} at tick {}", peer, tick);
+                        *desynced_at = Some(*tick);
+                    }
+                }
+                Some(_) => {}
+                None => log::warn!("No local checksum for tick {} from peer {}", tick, peer),
+            }
+
+            false
+        });
+    }
+
+    fn frame_mut(&mut self, tick: u64) -> &mut Vec<Option<Vec<InputEvent>>> {
+        let peer_count = self.peer_count;
+        self.frames
+            .entry(tick)
+            .or_insert_with(|| vec![None; peer_count])
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn local_peer(&self) -> usize {
+        self.local_peer
+    }
+
+    pub fn desynced_at(&self) -> Option<u64> {
+        self.desynced_at
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+}
+
+/// An in-process transport connecting peers through shared queues.
+pub struct LoopbackTransport {
+    peer: usize,
+    queues: Rc<RefCell<Vec<VecDeque<Message>>>>,
+}
+
+impl LoopbackTransport {
+    pub fn network(peer_count: usize) -> Vec<Self> {
+        let queues = Rc::new(RefCell::new(vec![VecDeque::new(); peer_count]));
+
+        (0..peer_count)
+            .map(|peer| Self {
+                peer,
+                queues: queues.clone(),
+            })
+            .collect()
+    }
+}
+
+impl Transport for LoopbackTransport {
+    fn send(&mut self, message: Message) {
+        let mut queues = self.queues.borrow_mut();
+
+        for (peer, queue) in queues.iter_mut().enumerate() {
+            if peer != self.peer {
+                queue.push_back(message.clone());
+            }
+        }
+    }
+
+    fn receive(&mut self) -> Option<Message> {
+        self.queues.borrow_mut()[self.peer].pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: u32 = 42;
+
+    fn session() -> Vec<Lockstep<LoopbackTransport>> {
+        let sources = [InputSource::Keyboard, InputSource::Keyboard];
+
+        LoopbackTransport::network(sources.len())
+            .into_iter()
+            .enumerate()
+            .map(|(peer, transport)| Lockstep::new(SEED, &sources, peer, transport))
+            .collect()
+    }
+
+    fn run(peers: &mut [Lockstep<LoopbackTransport>], ticks: u64) {
+        let target = peers[0].tick() + ticks;
+
+        while peers.iter().any(|peer| peer.tick() < target) {
+            for peer in peers.iter_mut() {
+                if peer.tick() < target {
+                    peer.advance(FIXED_DELTA);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn peers_stay_in_sync() {
+        let mut peers = session();
+
+        peers[0].record(InputEvent::KeyDown {
+            player: None,
+            key: "KeyD".to_string(),
+        });
+
+        peers[1].record(InputEvent::MouseDown {
+            player: 0,
+            x: 1.0,
+            y: 0.0,
+        });
+
+        run(&mut peers, CHECKSUM_INTERVAL * 3);
+
+        assert_eq!(peers[0].tick(), peers[1].tick());
+        assert_eq!(peers[0].game().state_hash(), peers[1].game().state_hash());
+        assert_eq!(peers[0].desynced_at(), None);
+        assert_eq!(peers[1].desynced_at(), None);
+    }
+
+    #[test]
+    fn peer_waits_for_remote_frames() {
+        let mut peers = session();
+
+        for _ in 0..10 {
+            peers[0].advance(FIXED_DELTA);
+        }
+
+        assert_eq!(peers[0].tick(), INPUT_DELAY);
+    }
+
+    #[test]
+    fn divergence_is_detected() {
+        let mut peers = session();
+
+        peers[1]
+            .game
+            .inputs(1)
+            .unwrap()
+            .key_down("KeyW".to_string());
+
+        run(&mut peers, CHECKSUM_INTERVAL * 2);
+
+        assert!(peers[0].desynced_at().is_some());
+        assert!(peers[1].desynced_at().is_some());
+    }
+}
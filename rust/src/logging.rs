@@ -1,5 +1,6 @@
-use log::{Level, Log, Metadata, Record};
-use wasm_bindgen::prelude::*;
+use log::{Log, Metadata, Record};
+
+use crate::platform;
 
 pub static LOGGER: &'static dyn Log = &NativeLog;
 
@@ -12,29 +13,8 @@ impl Log for NativeLog {
 
     fn log(&self, record: &Record) {
         let message = format!("{}", record.args());
-
-        match record.level() {
-            Level::Error => console_error(&message),
-            Level::Warn => console_warn(&message),
-            Level::Info => console_info(&message),
-            Level::Debug | Level::Trace => console_debug(&message),
-        }
+        platform::write_log(record.level(), &message);
     }
 
     fn flush(&self) {}
 }
-
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = console, js_name = debug)]
-    fn console_debug(s: &str);
-
-    #[wasm_bindgen(js_namespace = console, js_name = info)]
-    fn console_info(s: &str);
-
-    #[wasm_bindgen(js_namespace = console, js_name = warn)]
-    fn console_warn(s: &str);
-
-    #[wasm_bindgen(js_namespace = console, js_name = error)]
-    fn console_error(s: &str);
-}
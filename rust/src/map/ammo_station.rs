@@ -0,0 +1,41 @@
+use crate::components::sprite::sprite::Sprite;
+use crate::components::sprite::FrameSprites;
+use crate::graphics::texture;
+use crate::util::rect::Rect;
+use crate::util::vector::Vec2;
+use crate::{vec2, Mat3};
+
+const BULLET_COUNT: usize = 3;
+
+pub struct AmmoStation {
+    position: Vec2,
+}
+
+impl AmmoStation {
+    pub fn new(position: Vec2) -> Self {
+        Self { position }
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    pub fn draw(&self, timestamp: f64, sprites: &mut FrameSprites) {
+        for i in 0..BULLET_COUNT {
+            let x = (i as f64 - 1.0) * 0.1;
+            let y = 0.21 + (timestamp * 1.5 + i as f64).sin() * 0.05;
+
+            let transform = Mat3::transform(vec2(x, y), vec2(0.2, 0.2));
+
+            let bullet_sprite = Sprite::new_transformed(texture::bullet(), transform);
+            sprites.draw_sprite(self.position, bullet_sprite);
+        }
+
+        let sprite = Sprite::new_sized(texture::open_chest(), self.bounds().size);
+        sprites.draw_sprite(self.position, sprite);
+    }
+
+    pub fn bounds(&self) -> Rect {
+        Rect::focused(self.position, vec2(0.5, 0.5))
+    }
+}
@@ -9,6 +9,7 @@ use crate::components::sprite::FrameSprites;
 use crate::ecs::WorldExtensions;
 use crate::graphics::texture;
 use crate::gun::{Gun, GunSpec, GunSpecGenerator};
+use crate::util::random::RandomStreams;
 use crate::util::rect::Rect;
 use crate::util::vector::Vec2;
 use crate::{vec2, Mat3};
@@ -45,6 +46,19 @@ impl Chest {
         player.equip_gun(Gun::new(gun_spec));
     }
 
+    pub fn exhausted_players(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.exhausted_players.iter().copied()
+    }
+
+    pub fn restore(
+        &mut self,
+        gun: Rc<GunSpec>,
+        exhausted_players: impl IntoIterator<Item = Entity>,
+    ) {
+        self.gun = Some(gun);
+        self.exhausted_players = exhausted_players.into_iter().collect();
+    }
+
     pub fn gun(&self) -> Option<&GunSpec> {
         self.gun.as_ref().map(Rc::deref)
     }
@@ -56,7 +70,7 @@ impl Chest {
 
         let gun_spec = world
             .fetch_mut::<GunSpecGenerator>()
-            .generate(self.position);
+            .generate(world.fetch_mut::<RandomStreams>().loot(), self.position);
 
         self.gun = Some(Rc::new(gun_spec));
     }
@@ -69,12 +83,12 @@ impl Chest {
         self.position
     }
 
-    pub fn draw(&self, timestamp: f64, player: Option<Entity>, sprites: &mut FrameSprites) {
-        if let Some(player) = player {
-            if self.exhausted_players.contains(&player) {
-                return;
-            }
-        } else {
+    pub fn draw(&self, timestamp: f64, players: &[Entity], sprites: &mut FrameSprites) {
+        let visible_to_any = players
+            .iter()
+            .any(|player| !self.exhausted_players.contains(player));
+
+        if !visible_to_any {
             return;
         }
 
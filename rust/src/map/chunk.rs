@@ -1,6 +1,12 @@
+use crate::gun::GunUpgrade;
+use crate::map::ammo_station::AmmoStation;
 use crate::map::chest::Chest;
+use crate::map::perk_station::PerkStation;
+use crate::map::upgrade_bench::UpgradeBench;
 use crate::map::Tile;
+use crate::perk::Perk;
 use crate::util::coord::Coord;
+use crate::util::rect::{rect, Rect};
 use crate::util::vector::Vec2;
 use crate::vec2;
 
@@ -10,8 +16,12 @@ pub const CHUNK_SIZE_I32: i32 = CHUNK_SIZE as i32;
 pub struct Chunk {
     coord: Coord,
     data: Box<[[Tile; CHUNK_SIZE]; CHUNK_SIZE]>,
+    rooms: Vec<Rect>,
     spawners: Vec<Vec2>,
     chests: Vec<Chest>,
+    ammo_stations: Vec<AmmoStation>,
+    perk_stations: Vec<PerkStation>,
+    upgrade_benches: Vec<UpgradeBench>,
     decorations: Vec<Vec2>,
 }
 
@@ -22,8 +32,12 @@ impl Chunk {
         let mut result = Self {
             coord,
             data,
+            rooms: Vec::new(),
             spawners: Vec::new(),
             chests: Vec::new(),
+            ammo_stations: Vec::new(),
+            perk_stations: Vec::new(),
+            upgrade_benches: Vec::new(),
             decorations: Vec::new(),
         };
 
@@ -51,6 +65,15 @@ impl Chunk {
         )
     }
 
+    pub fn record_room(&mut self, position: Vec2, size: Vec2) {
+        let offset = self.chunk_start();
+        self.rooms.push(rect(position + offset, size));
+    }
+
+    pub fn rooms(&self) -> impl Iterator<Item = Rect> + '_ {
+        self.rooms.iter().copied()
+    }
+
     pub fn create_spawner(&mut self, position: Vec2) {
         let offset = self.chunk_start();
         self.spawners.push(position + offset);
@@ -66,6 +89,11 @@ impl Chunk {
         self.chests.push(chest);
     }
 
+    pub fn push_chest(&mut self, chest: Chest) -> &mut Chest {
+        self.chests.push(chest);
+        self.chests.last_mut().unwrap()
+    }
+
     pub fn chests(&self) -> impl Iterator<Item = &Chest> {
         self.chests.iter()
     }
@@ -74,6 +102,36 @@ impl Chunk {
         self.chests.iter_mut()
     }
 
+    pub fn create_ammo_station(&mut self, position: Vec2) {
+        let offset = self.chunk_start();
+        let ammo_station = AmmoStation::new(position + offset);
+        self.ammo_stations.push(ammo_station);
+    }
+
+    pub fn ammo_stations(&self) -> impl Iterator<Item = &AmmoStation> {
+        self.ammo_stations.iter()
+    }
+
+    pub fn create_perk_station(&mut self, position: Vec2, perk: Perk) {
+        let offset = self.chunk_start();
+        let perk_station = PerkStation::new(position + offset, perk);
+        self.perk_stations.push(perk_station);
+    }
+
+    pub fn perk_stations(&self) -> impl Iterator<Item = &PerkStation> {
+        self.perk_stations.iter()
+    }
+
+    pub fn create_upgrade_bench(&mut self, position: Vec2, upgrade: GunUpgrade, seed: u32) {
+        let offset = self.chunk_start();
+        let upgrade_bench = UpgradeBench::new(position + offset, upgrade, seed);
+        self.upgrade_benches.push(upgrade_bench);
+    }
+
+    pub fn upgrade_benches(&self) -> impl Iterator<Item = &UpgradeBench> {
+        self.upgrade_benches.iter()
+    }
+
     pub fn create_decoration(&mut self, position: Vec2) {
         let offset = self.chunk_start();
         self.decorations.push(position + offset);
@@ -5,7 +5,7 @@ use specs::{Entity, ReadExpect, System, World, WriteExpect};
 
 use crate::camera::Camera;
 use crate::components::sprite::FrameSprites;
-use crate::ecs::ReadControlledPlayer;
+use crate::ecs::ReadControlledPlayers;
 use crate::game::Timestamp;
 use crate::graphics::{texture, DrawBuffer, EntityRendererSettings};
 use crate::map::chunk::CHUNK_SIZE_I32;
@@ -30,7 +30,7 @@ pub struct DrawMapBaseData<'a> {
     camera: ReadExpect<'a, Camera>,
     buffer: WriteExpect<'a, DrawBuffer>,
     sprites: WriteExpect<'a, FrameSprites>,
-    controlled_player: ReadControlledPlayer<'a>,
+    controlled_players: ReadControlledPlayers<'a>,
 }
 
 impl<'a> System<'a> for DrawMapBase {
@@ -41,7 +41,7 @@ impl<'a> System<'a> for DrawMapBase {
         data.map.draw_dynamic(
             data.timestamp.0,
             *data.camera,
-            data.controlled_player.value(),
+            &data.controlled_players.values(),
             &mut data.sprites,
         );
     }
@@ -103,7 +103,7 @@ impl Map {
         &self,
         timestamp: f64,
         camera: Camera,
-        player: Option<Entity>,
+        players: &[Entity],
         sprites: &mut FrameSprites,
     ) {
         let min = camera.min_coord() - MAX_SPRITE_RADIUS;
@@ -116,7 +116,19 @@ impl Map {
             let chunk = self.chunks.at(chunk_coord);
 
             for chest in chunk.chests() {
-                chest.draw(timestamp, player, sprites);
+                chest.draw(timestamp, players, sprites);
+            }
+
+            for ammo_station in chunk.ammo_stations() {
+                ammo_station.draw(timestamp, sprites);
+            }
+
+            for perk_station in chunk.perk_stations() {
+                perk_station.draw(timestamp, sprites);
+            }
+
+            for upgrade_bench in chunk.upgrade_benches() {
+                upgrade_bench.draw(timestamp, sprites);
             }
         }
     }
@@ -4,13 +4,16 @@ use float_ord::FloatOrd;
 use fxhash::hash32;
 use pathfinding::prelude::{astar, dijkstra, kruskal};
 
+use crate::gun::GunUpgrade;
 use crate::map::chunk::{Chunk, CHUNK_SIZE, CHUNK_SIZE_I32};
 use crate::map::Tile;
+use crate::perk::Perk;
+use crate::platform::current_time;
 use crate::util::coord::{coord, Coord};
 use crate::util::random::Random;
 use crate::util::triangulation::triangulate;
 use crate::util::vector::Vec2;
-use crate::{current_time, vec2};
+use crate::vec2;
 
 const MAX_ROOM_ATTEMPTS: usize = CHUNK_SIZE.pow(2);
 
@@ -27,12 +30,38 @@ const EXTRA_EDGE_CHANCE: f64 = 0.33;
 
 const PATHFINDING_TUNNEL_COST: u32 = 5;
 
-const SIDE_LENGTH_PER_SPAWNER: i32 = 4;
+pub(super) const SIDE_LENGTH_PER_SPAWNER: i32 = 4;
 const DECORATION_CHANCE: f64 = 0.033;
-const CHEST_DISTANCE: f64 = 10.0;
+pub(super) const CHEST_DISTANCE: f64 = 10.0;
+const AMMO_STATION_DISTANCE: f64 = 20.0;
+const PERK_STATION_DISTANCE: f64 = 25.0;
+const UPGRADE_BENCH_DISTANCE: f64 = 25.0;
 
 const BARRIER_CHANCE: f64 = 0.5;
 
+/// Features placed at most once per room, away from other stations of the same kind.
+#[derive(Copy, Clone, Debug)]
+pub(super) enum Station {
+    Ammo,
+    Perk,
+    UpgradeBench,
+}
+
+impl Station {
+    /// In the order they were added, which is the order they're placed in, so that adding a kind
+    /// doesn't shift where any existing feature is placed.
+    pub(super) const ALL: [Station; 3] = [Station::Ammo, Station::Perk, Station::UpgradeBench];
+
+    /// Minimum distance between a room's center and stations of the same kind.
+    pub(super) fn distance(self) -> f64 {
+        match self {
+            Station::Ammo => AMMO_STATION_DISTANCE,
+            Station::Perk => PERK_STATION_DISTANCE,
+            Station::UpgradeBench => UPGRADE_BENCH_DISTANCE,
+        }
+    }
+}
+
 impl Chunk {
     pub fn generate(&mut self, seed: u32) {
         let start = current_time();
@@ -42,14 +71,18 @@ impl Chunk {
         let rooms = self.generate_rooms(&mut random);
         for room in &rooms {
             self.create_room(*room);
+            self.record_room(room.min_coord().start(), room.size());
         }
 
-        let awkward_points = Self::compute_awkward_points(&rooms);
+        let border_points = Self::compute_border_points();
+        let awkward_points = Self::compute_awkward_points(&rooms, &border_points);
 
-        self.connect_all_rooms(&mut random, &rooms, &awkward_points);
+        self.connect_all_rooms(&mut random, &rooms, &awkward_points, &border_points);
 
+        // Entries only avoid the border, so that they can't be blocked by this chunk's rooms and
+        // always meet the entries carved by the neighbouring chunk.
         for entry in self.chunk_entries() {
-            self.create_chunk_entry(entry, &awkward_points);
+            self.create_chunk_entry(entry, &border_points);
         }
 
         self.add_barriers(&rooms, &mut random);
@@ -84,9 +117,22 @@ impl Chunk {
         rooms
     }
 
-    fn compute_awkward_points(rooms: &[Room]) -> HashSet<Coord> {
+    fn compute_border_points() -> HashSet<Coord> {
         let mut result = HashSet::new();
 
+        for i in 0..CHUNK_SIZE_I32 {
+            result.insert(coord(i, 0));
+            result.insert(coord(0, i));
+            result.insert(coord(i, CHUNK_SIZE_I32 - 1));
+            result.insert(coord(CHUNK_SIZE_I32 - 1, i));
+        }
+
+        result
+    }
+
+    fn compute_awkward_points(rooms: &[Room], border_points: &HashSet<Coord>) -> HashSet<Coord> {
+        let mut result = border_points.clone();
+
         let mut insert = |x: i32, y: i32| result.insert(coord(x, y));
 
         for room in rooms {
@@ -107,13 +153,6 @@ impl Chunk {
             insert(room.x + room.width, room.y + room.height - 1);
         }
 
-        for i in 0..CHUNK_SIZE_I32 {
-            insert(i, 0);
-            insert(0, i);
-            insert(i, CHUNK_SIZE_I32 - 1);
-            insert(CHUNK_SIZE_I32 - 1, i);
-        }
-
         result
     }
 
@@ -122,6 +161,7 @@ impl Chunk {
         random: &mut Random,
         rooms: &[Room],
         awkward_points: &HashSet<Coord>,
+        border_points: &HashSet<Coord>,
     ) {
         let vertices = rooms
             .iter()
@@ -148,7 +188,10 @@ impl Chunk {
         let mst = kruskal(&edges);
 
         for (a, b, _) in mst {
-            self.create_path(*a, *b, awkward_points);
+            // Rooms boxed in by the corners of others are still connected by tunnelling past them.
+            if !self.create_path(*a, *b, awkward_points) {
+                self.create_path(*a, *b, border_points);
+            }
         }
 
         for (a, b, _) in edges {
@@ -234,12 +277,20 @@ impl Chunk {
     }
 
     fn add_features(&mut self, random: &mut Random, rooms: &[Room]) {
-        for room in rooms {
-            let mut feature_positions = Vec::new();
+        let mut room_feature_positions = vec![Vec::new(); rooms.len()];
+
+        // Spawners come first, so that nothing can crowd out a room's first spawner.
+        for (room, feature_positions) in rooms.iter().zip(&mut room_feature_positions) {
+            self.add_spawners(random, room, feature_positions);
+            self.add_chest(random, room, feature_positions);
+            self.add_decorations(random, room, feature_positions);
+        }
 
-            self.add_chest(random, room, &mut feature_positions);
-            self.add_spawners(random, room, &mut feature_positions);
-            self.add_decorations(random, room, &mut feature_positions);
+        // Stations come last so that they don't shift where any other feature is placed.
+        for station in Station::ALL {
+            for (room, feature_positions) in rooms.iter().zip(&mut room_feature_positions) {
+                self.add_station(station, random, room, feature_positions);
+            }
         }
     }
 
@@ -297,6 +348,58 @@ impl Chunk {
         true
     }
 
+    fn add_station(
+        &mut self,
+        station: Station,
+        random: &mut Random,
+        room: &Room,
+        feature_positions: &mut Vec<Vec2>,
+    ) {
+        if !self.can_add_station(station, room) {
+            return;
+        }
+
+        if let Some(position) = self.available_feature_position(random, room, feature_positions) {
+            match station {
+                Station::Ammo => self.create_ammo_station(position),
+                Station::Perk => self.create_perk_station(position, Perk::random(random)),
+                Station::UpgradeBench => {
+                    let upgrade = *random.element(&GunUpgrade::ALL);
+                    self.create_upgrade_bench(position, upgrade, random.next_u32());
+                }
+            }
+        }
+    }
+
+    fn can_add_station(&self, station: Station, room: &Room) -> bool {
+        if room.is_starting {
+            return false;
+        }
+
+        let position = room.center().start() + self.chunk_start();
+
+        self.station_positions(station)
+            .into_iter()
+            .all(|other| Vec2::distance_squared(position, other) >= station.distance().powi(2))
+    }
+
+    fn station_positions(&self, station: Station) -> Vec<Vec2> {
+        match station {
+            Station::Ammo => self
+                .ammo_stations()
+                .map(|ammo_station| ammo_station.position())
+                .collect(),
+            Station::Perk => self
+                .perk_stations()
+                .map(|perk_station| perk_station.position())
+                .collect(),
+            Station::UpgradeBench => self
+                .upgrade_benches()
+                .map(|upgrade_bench| upgrade_bench.position())
+                .collect(),
+        }
+    }
+
     fn add_spawners(
         &mut self,
         random: &mut Random,
@@ -355,17 +458,23 @@ impl Chunk {
         }
     }
 
-    fn create_path(&mut self, start: Coord, end: Coord, awkward_points: &HashSet<Coord>) {
-        let successors = |coord: &Coord| self.pathfinding_successors(*coord, awkward_points);
+    /// Tunnels from `start` to `end` without crossing `avoided_points`, returning whether a path
+    /// was found.
+    fn create_path(&mut self, start: Coord, end: Coord, avoided_points: &HashSet<Coord>) -> bool {
+        let successors = |coord: &Coord| self.pathfinding_successors(*coord, avoided_points);
         let heuristic = |coord: &Coord| coord.distance(end);
 
-        if let Some((nodes, _)) = astar(&start, successors, heuristic, |coord| *coord == end) {
-            self.fill_path(nodes);
+        match astar(&start, successors, heuristic, |coord| *coord == end) {
+            Some((nodes, _)) => {
+                self.fill_path(nodes);
+                true
+            }
+            None => false,
         }
     }
 
-    fn create_chunk_entry(&mut self, start: Coord, awkward_points: &HashSet<Coord>) {
-        let successors = |coord: &Coord| self.pathfinding_successors(*coord, awkward_points);
+    fn create_chunk_entry(&mut self, start: Coord, avoided_points: &HashSet<Coord>) {
+        let successors = |coord: &Coord| self.pathfinding_successors(*coord, avoided_points);
         let success = |coord: &Coord| self.at(*coord).is_walkable();
 
         if let Some((nodes, _)) = dijkstra(&start, successors, success) {
@@ -376,14 +485,14 @@ impl Chunk {
     fn pathfinding_successors<'a>(
         &'a self,
         coord: Coord,
-        awkward_points: &'a HashSet<Coord>,
+        avoided_points: &'a HashSet<Coord>,
     ) -> impl Iterator<Item = (Coord, u32)> + 'a {
         let options = [coord.top(), coord.bottom(), coord.left(), coord.right()];
 
         options
             .into_iter()
             .filter(|coord| Self::in_bounds(*coord))
-            .filter(|coord| !awkward_points.contains(coord))
+            .filter(|coord| !avoided_points.contains(coord))
             .map(|coord| (coord, self.pathfinding_cost(coord)))
     }
 
@@ -458,8 +567,9 @@ impl Room {
     pub fn random(random: &mut Random) -> Self {
         let (width, height) = Self::dimensions(random);
 
-        let x_range = 0..(CHUNK_SIZE as u32 - width as u32 - 1);
-        let y_range = 0..(CHUNK_SIZE as u32 - height as u32 - 1);
+        // Rooms keep off the chunk border, which only chunk entries cross.
+        let x_range = 1..(CHUNK_SIZE as u32 - width as u32 - 1);
+        let y_range = 1..(CHUNK_SIZE as u32 - height as u32 - 1);
 
         let x = random.next_u32_in(&x_range) as i32;
         let y = random.next_u32_in(&y_range) as i32;
@@ -494,6 +604,10 @@ impl Room {
         vec2(x, y)
     }
 
+    pub fn size(&self) -> Vec2 {
+        vec2(self.width as f64, self.height as f64)
+    }
+
     pub fn center(&self) -> Coord {
         coord(self.x + self.width / 2, self.y + self.height / 2)
     }
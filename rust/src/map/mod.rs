@@ -3,33 +3,55 @@ use std::cell::{RefCell, UnsafeCell};
 use float_ord::FloatOrd;
 use fxhash::FxHashMap;
 use pathfinding::prelude::astar;
+use serde::{Deserialize, Serialize};
 
+use crate::map::ammo_station::AmmoStation;
 use crate::map::chest::Chest;
+use crate::map::perk_station::PerkStation;
+use crate::map::upgrade_bench::UpgradeBench;
 use chunk::{Chunk, CHUNK_SIZE_I32};
 use draw::{RenderRegion, RENDER_REGION_SIZE};
+use visibility::VisibilityCache;
 
 use crate::util::coord::Coord;
 use crate::util::rect::Rect;
 use crate::util::vector::Vec2;
 
+pub mod ammo_station;
 pub mod chest;
 mod chunk;
 pub mod draw;
 mod generation;
+pub mod perk_station;
+pub mod snapshot;
+#[cfg(test)]
+mod tests;
+pub mod upgrade_bench;
+pub mod visibility;
 
 pub struct Map {
     chunks: ChunkStorage,
+    changed_tiles: FxHashMap<Coord, Tile>,
+    dropped_chests: Vec<Vec2>,
     render_regions: RefCell<FxHashMap<(i32, i32), RenderRegion>>,
+    visibility: RefCell<VisibilityCache>,
 }
 
 impl Map {
     pub fn new(seed: u32) -> Self {
         Self {
             chunks: ChunkStorage::new(seed),
+            changed_tiles: FxHashMap::default(),
+            dropped_chests: Vec::new(),
             render_regions: RefCell::new(FxHashMap::default()),
+            visibility: RefCell::new(VisibilityCache::default()),
         }
     }
 
+    pub fn seed(&self) -> u32 {
+        self.chunks.seed
+    }
+
     pub fn at(&self, coord: Coord) -> Tile {
         let offset = coord.offset(CHUNK_SIZE_I32 / 2, CHUNK_SIZE_I32 / 2);
         let (chunk_coord, local_coord) = offset.chunk(CHUNK_SIZE_I32);
@@ -45,6 +67,9 @@ impl Map {
         let chunk = self.chunks.at_mut(chunk_coord);
         *chunk.at_mut(local_coord) = tile;
 
+        self.changed_tiles.insert(coord, tile);
+        self.visibility.borrow_mut().invalidate(coord);
+
         let render_region_x = coord.x().div_euclid(RENDER_REGION_SIZE);
         let render_region_y = coord.y().div_euclid(RENDER_REGION_SIZE);
 
@@ -53,6 +78,37 @@ impl Map {
             .remove(&(render_region_x, render_region_y));
     }
 
+    pub fn changed_tiles(&self) -> impl Iterator<Item = (Coord, Tile)> + '_ {
+        self.changed_tiles
+            .iter()
+            .map(|(coord, tile)| (*coord, *tile))
+    }
+
+    /// Places a chest that wasn't part of the generated map, such as loot dropped by a boss.
+    pub fn drop_chest(&mut self, position: Vec2) -> &mut Chest {
+        let offset = position
+            .coord()
+            .offset(CHUNK_SIZE_I32 / 2, CHUNK_SIZE_I32 / 2);
+        let (chunk_coord, _) = offset.chunk(CHUNK_SIZE_I32);
+
+        self.dropped_chests.push(position);
+
+        self.chunks
+            .at_mut(chunk_coord)
+            .push_chest(Chest::new(position))
+    }
+
+    pub fn dropped_chests(&self) -> impl Iterator<Item = Vec2> + '_ {
+        self.dropped_chests.iter().copied()
+    }
+
+    pub fn opened_chests(&self) -> impl Iterator<Item = &Chest> {
+        self.chunks
+            .generated()
+            .flat_map(|chunk| chunk.chests())
+            .filter(|chest| chest.is_open())
+    }
+
     fn chunk_coords_in(&self, rect: Rect) -> impl Iterator<Item = Coord> {
         let min_coord = rect.min().coord();
         let max_coord = rect.max().coord();
@@ -93,12 +149,98 @@ impl Map {
             .filter(move |chest| rect.contains(chest.position()))
     }
 
+    pub fn ammo_stations_in(&self, rect: Rect) -> impl Iterator<Item = &AmmoStation> {
+        self.chunks_in(rect)
+            .flat_map(|chunk| chunk.ammo_stations())
+            .filter(move |ammo_station| rect.contains(ammo_station.position()))
+    }
+
+    pub fn perk_stations_in(&self, rect: Rect) -> impl Iterator<Item = &PerkStation> {
+        self.chunks_in(rect)
+            .flat_map(|chunk| chunk.perk_stations())
+            .filter(move |perk_station| rect.contains(perk_station.position()))
+    }
+
+    pub fn upgrade_benches_in(&self, rect: Rect) -> impl Iterator<Item = &UpgradeBench> {
+        self.chunks_in(rect)
+            .flat_map(|chunk| chunk.upgrade_benches())
+            .filter(move |upgrade_bench| rect.contains(upgrade_bench.position()))
+    }
+
+    pub fn rooms_in(&self, rect: Rect) -> impl Iterator<Item = Rect> + '_ {
+        self.chunks_in(rect)
+            .flat_map(|chunk| chunk.rooms())
+            .filter(move |room| room.touches(rect))
+    }
+
     pub fn decorations_in(&self, rect: Rect) -> impl Iterator<Item = Vec2> + '_ {
         self.chunks_in(rect)
             .flat_map(|chunk| chunk.decorations())
             .filter(move |position| rect.contains(*position))
     }
 
+    /// Steps through the tiles along a ray until it enters one that isn't walkable, giving up once
+    /// it travelled further than `max_distance`.
+    pub fn raycast(&self, origin: Vec2, direction: Vec2, max_distance: f64) -> Option<RaycastHit> {
+        let mut coord = origin.coord();
+
+        if !self.at(coord).is_walkable() {
+            return Some(RaycastHit {
+                coord,
+                position: origin,
+                distance: 0.0,
+            });
+        }
+
+        let direction = direction.normalize_or_zero();
+        if direction.is_zero() {
+            return None;
+        }
+
+        let axis = |origin: f64, direction: f64, start: i32| {
+            if direction > 0.0 {
+                (
+                    1,
+                    (start as f64 + 1.0 - origin) / direction,
+                    1.0 / direction,
+                )
+            } else if direction < 0.0 {
+                (-1, (origin - start as f64) / -direction, -1.0 / direction)
+            } else {
+                (0, f64::INFINITY, f64::INFINITY)
+            }
+        };
+
+        let (step_x, mut next_x, delta_x) = axis(origin.x(), direction.x(), coord.x());
+        let (step_y, mut next_y, delta_y) = axis(origin.y(), direction.y(), coord.y());
+
+        loop {
+            let distance;
+
+            if next_x < next_y {
+                distance = next_x;
+                coord = coord.offset(step_x, 0);
+                next_x += delta_x;
+            } else {
+                distance = next_y;
+                coord = coord.offset(0, step_y);
+                next_y += delta_y;
+            }
+
+            if distance > max_distance {
+                return None;
+            }
+
+            if !self.at(coord).is_walkable() {
+                return Some(RaycastHit {
+                    coord,
+                    position: origin + direction * distance,
+                    distance,
+                });
+            }
+        }
+    }
+
     pub fn pathfind<T, I>(
         &self,
         start: Coord,
@@ -126,27 +268,7 @@ impl Map {
         };
 
         let successors = |coord: &Coord| {
-            let directs = [coord.top(), coord.left(), coord.right(), coord.bottom()];
-
-            let directs = directs
-                .into_iter()
-                .filter(|coord| self.at(*coord).is_walkable())
-                .map(|coord| (coord, 10));
-
-            let diagonals = [
-                [coord.top_right(), coord.top(), coord.right()],
-                [coord.bottom_right(), coord.bottom(), coord.right()],
-                [coord.bottom_left(), coord.bottom(), coord.left()],
-                [coord.top_left(), coord.top(), coord.left()],
-            ];
-
-            let diagonals = diagonals
-                .into_iter()
-                .filter(|coords| coords.iter().all(|coord| self.at(*coord).is_walkable()))
-                .map(|[coord, _, _]| (coord, 14));
-
-            directs
-                .chain(diagonals)
+            self.walkable_neighbours(*coord)
                 .filter(|(coord, _)| in_range(*coord))
         };
 
@@ -165,6 +287,38 @@ impl Map {
         let result = astar(&start, successors, heuristic, success);
         result.map(|(path, cost)| (path, cost.div_ceil(10) as usize))
     }
+
+    /// Walkable tiles a step away, with the cost of stepping there. Diagonal steps may not cut
+    /// corners.
+    pub fn walkable_neighbours(&self, coord: Coord) -> impl Iterator<Item = (Coord, u32)> + '_ {
+        let directs = [coord.top(), coord.left(), coord.right(), coord.bottom()];
+
+        let directs = directs
+            .into_iter()
+            .filter(|coord| self.at(*coord).is_walkable())
+            .map(|coord| (coord, 10));
+
+        let diagonals = [
+            [coord.top_right(), coord.top(), coord.right()],
+            [coord.bottom_right(), coord.bottom(), coord.right()],
+            [coord.bottom_left(), coord.bottom(), coord.left()],
+            [coord.top_left(), coord.top(), coord.left()],
+        ];
+
+        let diagonals = diagonals
+            .into_iter()
+            .filter(|coords| coords.iter().all(|coord| self.at(*coord).is_walkable()))
+            .map(|[coord, _, _]| (coord, 14));
+
+        directs.chain(diagonals)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RaycastHit {
+    pub coord: Coord,
+    pub position: Vec2,
+    pub distance: f64,
 }
 
 const CHUNK_LOOKUP_CACHE_SIZE: usize = 2;
@@ -192,6 +346,11 @@ impl ChunkStorage {
         unsafe { &mut *self.lookup(coord) }
     }
 
+    pub fn generated(&self) -> impl Iterator<Item = &Chunk> {
+        let chunks = unsafe { &*self.chunks.get() };
+        chunks.values().map(|chunk| chunk.as_ref())
+    }
+
     fn lookup(&self, coord: Coord) -> *mut Chunk {
         let cache = unsafe { &mut *self.chunk_lookup_cache.get() };
 
@@ -220,7 +379,7 @@ impl ChunkStorage {
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Tile {
     Floor,
     Wall,
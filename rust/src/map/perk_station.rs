@@ -0,0 +1,41 @@
+use crate::components::sprite::sprite::Sprite;
+use crate::components::sprite::FrameSprites;
+use crate::graphics::texture;
+use crate::perk::Perk;
+use crate::util::rect::Rect;
+use crate::util::vector::Vec2;
+use crate::{vec2, Mat3};
+
+pub struct PerkStation {
+    position: Vec2,
+    perk: Perk,
+}
+
+impl PerkStation {
+    pub fn new(position: Vec2, perk: Perk) -> Self {
+        Self { position, perk }
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    pub fn perk(&self) -> Perk {
+        self.perk
+    }
+
+    pub fn draw(&self, timestamp: f64, sprites: &mut FrameSprites) {
+        let y = 0.21 + (timestamp * 1.5).sin() * 0.05;
+        let transform = Mat3::transform(vec2(0.0, y), vec2(0.3, 0.3));
+
+        let perk_sprite = Sprite::new_transformed(self.perk.texture(), transform);
+        sprites.draw_sprite(self.position, perk_sprite);
+
+        let sprite = Sprite::new_sized(texture::open_chest(), self.bounds().size);
+        sprites.draw_sprite(self.position, sprite);
+    }
+
+    pub fn bounds(&self) -> Rect {
+        Rect::focused(self.position, vec2(0.5, 0.5))
+    }
+}
@@ -0,0 +1,164 @@
+use serde::Serialize;
+
+use crate::map::{Map, Tile};
+use crate::util::coord::{coord, Coord};
+use crate::util::rect::{rect, Rect};
+use crate::util::vector::Vec2;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MapSnapshot {
+    seed: u32,
+    min: Coord,
+    max: Coord,
+    rows: Vec<String>,
+    rooms: Vec<Rect>,
+    spawners: Vec<Vec2>,
+    chests: Vec<SnapshotChest>,
+    decorations: Vec<Vec2>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotChest {
+    position: Vec2,
+    open: bool,
+}
+
+impl MapSnapshot {
+    pub fn generate(seed: u32, min: Coord, max: Coord) -> Self {
+        Map::new(seed).snapshot(min, max)
+    }
+
+    fn capture(map: &Map, min: Coord, max: Coord) -> Self {
+        let max = max.max(min);
+
+        let rows = (min.y()..=max.y())
+            .rev()
+            .map(|y| {
+                (min.x()..=max.x())
+                    .map(|x| tile_char(map.at(coord(x, y))))
+                    .collect()
+            })
+            .collect();
+
+        let area = rect(min.start(), max.end() - min.start());
+
+        let mut rooms = map.rooms_in(area).collect::<Vec<_>>();
+        let mut spawners = map.spawners_in(area).collect::<Vec<_>>();
+        let mut decorations = map.decorations_in(area).collect::<Vec<_>>();
+        let mut chests = map
+            .chests_in(area)
+            .map(|chest| SnapshotChest {
+                position: chest.position(),
+                open: chest.is_open(),
+            })
+            .collect::<Vec<_>>();
+
+        sort_positions(&mut rooms, |room| room.position);
+        sort_positions(&mut spawners, |position| *position);
+        sort_positions(&mut decorations, |position| *position);
+        sort_positions(&mut chests, |chest| chest.position);
+
+        Self {
+            seed: map.seed(),
+            min,
+            max,
+            rows,
+            rooms,
+            spawners,
+            chests,
+            decorations,
+        }
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn min(&self) -> Coord {
+        self.min
+    }
+
+    pub fn max(&self) -> Coord {
+        self.max
+    }
+
+    pub fn rooms(&self) -> &[Rect] {
+        &self.rooms
+    }
+
+    pub fn spawners(&self) -> &[Vec2] {
+        &self.spawners
+    }
+
+    pub fn decorations(&self) -> &[Vec2] {
+        &self.decorations
+    }
+
+    pub fn chest_positions(&self) -> impl Iterator<Item = Vec2> + '_ {
+        self.chests.iter().map(|chest| chest.position)
+    }
+
+    pub fn to_ascii(&self) -> String {
+        let mut grid = self
+            .rows
+            .iter()
+            .map(|row| row.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let features = self
+            .decorations
+            .iter()
+            .map(|position| (*position, '*'))
+            .chain(self.spawners.iter().map(|position| (*position, 'S')))
+            .chain(self.chests.iter().map(|chest| {
+                let symbol = if chest.open { 'c' } else { 'C' };
+                (chest.position, symbol)
+            }));
+
+        for (position, symbol) in features {
+            let coord = position.coord();
+            let column = (coord.x() - self.min.x()) as usize;
+            let row = (self.max.y() - coord.y()) as usize;
+
+            if let Some(cell) = grid.get_mut(row).and_then(|row| row.get_mut(column)) {
+                *cell = symbol;
+            }
+        }
+
+        let mut result = String::new();
+
+        for row in grid {
+            result.extend(row);
+            result.push('\n');
+        }
+
+        result
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+impl Map {
+    pub fn snapshot(&self, min: Coord, max: Coord) -> MapSnapshot {
+        MapSnapshot::capture(self, min, max)
+    }
+}
+
+fn tile_char(tile: Tile) -> char {
+    match tile {
+        Tile::Floor => '.',
+        Tile::Wall => '#',
+        Tile::Barrier => '=',
+    }
+}
+
+fn sort_positions<T>(items: &mut [T], position: impl Fn(&T) -> Vec2) {
+    items.sort_by(|a, b| {
+        let (a, b) = (position(a), position(b));
+        (a.y(), a.x()).partial_cmp(&(b.y(), b.x())).unwrap()
+    });
+}
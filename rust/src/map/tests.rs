@@ -0,0 +1,468 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::map::chunk::{CHUNK_SIZE, CHUNK_SIZE_I32};
+use crate::map::generation::{Station, CHEST_DISTANCE, SIDE_LENGTH_PER_SPAWNER};
+use crate::map::{Map, Tile};
+use crate::util::coord::{coord, Coord};
+use crate::util::rect::{rect, Rect};
+use crate::util::vector::Vec2;
+use crate::vec2;
+
+const SEEDS: [u32; 8] = [0, 1, 2, 7, 42, 1337, 65535, 3735928559];
+const GOLDEN_SEEDS: [u32; 4] = [0, 7, 42, 3735928559];
+
+const CHUNK_RADIUS: i32 = 1;
+const PATHFIND_THRESHOLD: f64 = 1000.0;
+
+fn chunk_min(chunk: Coord) -> Coord {
+    chunk * CHUNK_SIZE_I32 - CHUNK_SIZE_I32 / 2
+}
+
+fn chunk_bounds(chunk: Coord) -> Rect {
+    let size = CHUNK_SIZE as f64;
+    rect(chunk_min(chunk).start(), vec2(size, size))
+}
+
+fn chunks() -> impl Iterator<Item = Coord> {
+    Coord::between_inclusive(
+        coord(-CHUNK_RADIUS, -CHUNK_RADIUS),
+        coord(CHUNK_RADIUS, CHUNK_RADIUS),
+    )
+}
+
+fn region() -> Rect {
+    let min = chunk_bounds(coord(-CHUNK_RADIUS, -CHUNK_RADIUS));
+    let max = chunk_bounds(coord(CHUNK_RADIUS, CHUNK_RADIUS));
+    rect(min.min(), max.max() - min.min())
+}
+
+fn room_center(room: Rect) -> Coord {
+    let half = (room.size / 2.0).zip_with(Vec2::zero(), |value, _| value.floor());
+    (room.position + half).coord()
+}
+
+fn starting_room(map: &Map) -> Rect {
+    let spawn = vec2(0.5, 0.5);
+
+    map.rooms_in(Rect::focused(spawn, Vec2::one()))
+        .find(|room| room.contains(spawn))
+        .expect("no starting room")
+}
+
+fn room_perimeter(room: Rect) -> impl Iterator<Item = Coord> {
+    let min = room.min().coord() - 1;
+    let max = room.max().coord();
+
+    Coord::between_inclusive(min, max).filter(move |coord| {
+        coord.x() == min.x() || coord.x() == max.x() || coord.y() == min.y() || coord.y() == max.y()
+    })
+}
+
+fn remove_barriers(map: &mut Map, coords: impl Iterator<Item = Coord>) {
+    for coord in coords {
+        if map.at(coord) == Tile::Barrier {
+            map.set(coord, Tile::Floor);
+        }
+    }
+}
+
+fn is_reachable(map: &Map, start: Coord, target: Coord) -> bool {
+    map.pathfind(start, || [target].into_iter(), PATHFIND_THRESHOLD)
+        .is_some()
+}
+
+/// Coords within a chunk of the tested region, so that paths between its rooms can leave it.
+fn surrounding_coords() -> impl Iterator<Item = Coord> {
+    let area = region().expand(CHUNK_SIZE as f64);
+    Coord::between_inclusive(area.min().coord(), area.max().coord() - 1)
+}
+
+#[test]
+fn rooms_are_reachable_from_start() {
+    for seed in SEEDS {
+        let mut map = Map::new(seed);
+        remove_barriers(&mut map, surrounding_coords());
+
+        let start = room_center(starting_room(&map));
+
+        let unreachable = chunks()
+            .flat_map(|chunk| map.rooms_in(chunk_bounds(chunk)).collect::<Vec<_>>())
+            .map(room_center)
+            .filter(|target| !is_reachable(&map, start, *target))
+            .collect::<Vec<_>>();
+
+        assert!(
+            unreachable.is_empty(),
+            "seed {}: rooms at {:?} unreachable from start",
+            seed,
+            unreachable
+        );
+    }
+}
+
+#[test]
+fn start_is_enclosed_by_removable_barriers() {
+    for seed in SEEDS {
+        let mut map = Map::new(seed);
+        let start_room = starting_room(&map);
+        let start = room_center(start_room);
+
+        let barriers = room_perimeter(start_room)
+            .filter(|coord| map.at(*coord) == Tile::Barrier)
+            .count();
+
+        assert!(barriers > 0, "seed {}: starting room has no barriers", seed);
+
+        for coord in room_perimeter(start_room) {
+            assert_ne!(
+                map.at(coord),
+                Tile::Floor,
+                "seed {}: starting room has an open exit at {:?}",
+                seed,
+                coord
+            );
+        }
+
+        remove_barriers(&mut map, room_perimeter(start_room));
+
+        let escapable = map
+            .rooms_in(chunk_bounds(coord(0, 0)))
+            .filter(|room| *room != start_room)
+            .any(|room| is_reachable(&map, start, room_center(room)));
+
+        assert!(escapable, "seed {}: starting room is fully blocked", seed);
+    }
+}
+
+/// Where a border is open on its near side, given by `near`, and where it is open on its far
+/// side, reached by stepping `across` it from the near side.
+fn border_openings(
+    map: &Map,
+    near: impl Iterator<Item = Coord>,
+    across: impl Fn(&Coord) -> Coord,
+) -> (Vec<Coord>, Vec<Coord>) {
+    let near = near.collect::<Vec<_>>();
+
+    let open = |coord: Coord| map.at(coord).is_walkable();
+
+    let near_open = near.iter().copied().filter(|coord| open(*coord)).collect();
+    let far_open = near
+        .iter()
+        .copied()
+        .filter(|coord| open(across(coord)))
+        .collect();
+
+    (near_open, far_open)
+}
+
+#[test]
+fn chunk_entries_line_up_across_borders() {
+    for seed in SEEDS {
+        let map = Map::new(seed);
+
+        for chunk in chunks() {
+            let min = chunk_min(chunk);
+            let max = min + (CHUNK_SIZE_I32 - 1);
+
+            let right = (min.y()..=max.y()).map(|y| coord(max.x(), y));
+            let (near, far) = border_openings(&map, right, Coord::right);
+
+            assert!(
+                !near.is_empty(),
+                "seed {}: chunk {:?} has no right entries",
+                seed,
+                chunk
+            );
+            assert_eq!(
+                near, far,
+                "seed {}: chunk {:?} right entries don't line up",
+                seed, chunk
+            );
+
+            let top = (min.x()..=max.x()).map(|x| coord(x, max.y()));
+            let (near, far) = border_openings(&map, top, Coord::top);
+
+            assert!(
+                !near.is_empty(),
+                "seed {}: chunk {:?} has no top entries",
+                seed,
+                chunk
+            );
+            assert_eq!(
+                near, far,
+                "seed {}: chunk {:?} top entries don't line up",
+                seed, chunk
+            );
+        }
+    }
+}
+
+#[test]
+fn spawners_match_room_density() {
+    for seed in SEEDS {
+        let map = Map::new(seed);
+        let start_room = starting_room(&map);
+
+        for chunk in chunks() {
+            let bounds = chunk_bounds(chunk);
+            let spawners = map.spawners_in(bounds).collect::<Vec<_>>();
+
+            let rooms = map
+                .rooms_in(bounds)
+                .filter(|room| bounds.contains(room.position));
+
+            for room in rooms {
+                let count = spawners
+                    .iter()
+                    .filter(|spawner| room.contains(**spawner))
+                    .count();
+
+                let side = room.size.x().max(room.size.y()) as i32;
+                let expected = if room == start_room {
+                    1
+                } else {
+                    (side / SIDE_LENGTH_PER_SPAWNER) as usize
+                };
+
+                // Further spawners may be skipped when no free position is found in time, but
+                // the first one always fits.
+                assert!(
+                    count >= expected.min(1) && count <= expected,
+                    "seed {}: room at {:?} has {} spawners, expected {} to {}",
+                    seed,
+                    room.position,
+                    count,
+                    expected.min(1),
+                    expected
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn chests_respect_chest_distance() {
+    for seed in SEEDS {
+        let map = Map::new(seed);
+        let start_room = starting_room(&map);
+
+        for chunk in chunks() {
+            let bounds = chunk_bounds(chunk);
+            let rooms = map.rooms_in(bounds).collect::<Vec<_>>();
+            let chests = map
+                .chests_in(bounds)
+                .map(|chest| chest.position())
+                .collect::<Vec<_>>();
+
+            for (i, chest) in chests.iter().enumerate() {
+                let room = rooms
+                    .iter()
+                    .find(|room| room.contains(*chest))
+                    .expect("chest outside of a room");
+
+                assert_ne!(*room, start_room, "seed {}: chest in starting room", seed);
+
+                let center = room_center(*room).start();
+
+                for earlier in &chests[..i] {
+                    assert!(
+                        Vec2::distance(center, *earlier) >= CHEST_DISTANCE,
+                        "seed {}: chest at {:?} too close to chest at {:?}",
+                        seed,
+                        chest,
+                        earlier
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn station_positions(map: &Map, station: Station, bounds: Rect) -> Vec<Vec2> {
+    match station {
+        Station::Ammo => map
+            .ammo_stations_in(bounds)
+            .map(|ammo_station| ammo_station.position())
+            .collect(),
+        Station::Perk => map
+            .perk_stations_in(bounds)
+            .map(|perk_station| perk_station.position())
+            .collect(),
+        Station::UpgradeBench => map
+            .upgrade_benches_in(bounds)
+            .map(|upgrade_bench| upgrade_bench.position())
+            .collect(),
+    }
+}
+
+#[test]
+fn stations_respect_station_distance() {
+    for station in Station::ALL {
+        for seed in SEEDS {
+            let map = Map::new(seed);
+            let start_room = starting_room(&map);
+
+            for chunk in chunks() {
+                let bounds = chunk_bounds(chunk);
+                let rooms = map.rooms_in(bounds).collect::<Vec<_>>();
+                let positions = station_positions(&map, station, bounds);
+
+                for (i, position) in positions.iter().enumerate() {
+                    let room = rooms
+                        .iter()
+                        .find(|room| room.contains(*position))
+                        .unwrap_or_else(|| panic!("{:?} station outside of a room", station));
+
+                    assert_ne!(
+                        *room, start_room,
+                        "seed {}: {:?} station in starting room",
+                        seed, station
+                    );
+
+                    let center = room_center(*room).start();
+
+                    for earlier in &positions[..i] {
+                        assert!(
+                            Vec2::distance(center, *earlier) >= station.distance(),
+                            "seed {}: {:?} station at {:?} too close to one at {:?}",
+                            seed,
+                            station,
+                            position,
+                            earlier
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn raycasts_stop_at_starting_room_walls() {
+    for seed in SEEDS {
+        let map = Map::new(seed);
+        let start_room = starting_room(&map);
+        let origin = room_center(start_room).center();
+        let max_distance = start_room.size.length() + 2.0;
+
+        for direction in [
+            Vec2::right(),
+            Vec2::left(),
+            Vec2::top(),
+            Vec2::bottom(),
+            vec2(0.6, 0.8),
+            vec2(-0.8, -0.6),
+        ] {
+            let hit = map
+                .raycast(origin, direction, max_distance)
+                .unwrap_or_else(|| panic!("seed {}: ray {:?} left the room", seed, direction));
+
+            assert_ne!(map.at(hit.coord), Tile::Floor);
+            assert!(
+                room_perimeter(start_room).any(|coord| coord == hit.coord),
+                "seed {}: ray {:?} stopped at {:?} inside the room",
+                seed,
+                direction,
+                hit.coord
+            );
+            assert!(hit.coord.bounds().expand(1e-9).contains(hit.position));
+        }
+
+        assert_eq!(map.raycast(origin, Vec2::right(), 0.0), None);
+    }
+}
+
+fn room_coords(room: Rect) -> impl Iterator<Item = Coord> {
+    Coord::between_inclusive(room.min().coord(), room.max().coord() - 1)
+}
+
+#[test]
+fn starting_room_is_visible_but_enclosed() {
+    for seed in SEEDS {
+        let mut map = Map::new(seed);
+        let start_room = starting_room(&map);
+        let origin = room_center(start_room);
+        let radius = start_room.size.length().ceil() as i32 + 2;
+
+        let visible = map.visible_tiles(origin, radius);
+
+        for coord in room_coords(start_room) {
+            assert!(
+                visible.contains(&coord),
+                "seed {}: {:?} in the starting room is hidden",
+                seed,
+                coord
+            );
+            assert!(map.line_of_sight(origin.center(), coord.center()));
+        }
+
+        let perimeter = room_perimeter(start_room).collect::<Vec<_>>();
+
+        for coord in visible.iter() {
+            assert!(
+                start_room.contains(coord.center()) || perimeter.contains(coord),
+                "seed {}: {:?} outside the starting room is visible",
+                seed,
+                coord
+            );
+        }
+
+        let outside = perimeter[0] - 1;
+        assert!(!map.line_of_sight(origin.center(), outside.center()));
+
+        remove_barriers(&mut map, perimeter.iter().copied());
+
+        let opened = map.visible_tiles(origin, radius);
+        assert!(
+            opened.len() > visible.len(),
+            "seed {}: removing barriers didn't widen the view",
+            seed
+        );
+    }
+}
+
+fn golden_path(seed: u32) -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("src/map/golden");
+    path.push(format!("seed-{}.json", seed));
+    path
+}
+
+/// Covers every chunk of the tested region, including features the tiles alone don't show.
+#[test]
+fn generation_matches_golden_snapshots() {
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+
+    for seed in GOLDEN_SEEDS {
+        let min = region().min().coord();
+        let max = region().max().coord() - 1;
+
+        let snapshot = Map::new(seed).snapshot(min, max).to_json();
+        let path = golden_path(seed);
+
+        if update {
+            fs::write(&path, &snapshot).unwrap();
+            continue;
+        }
+
+        let golden = fs::read_to_string(&path)
+            .unwrap_or_else(|_| panic!("missing golden {:?}, run with UPDATE_GOLDEN=1", path));
+
+        let line = golden
+            .lines()
+            .zip(snapshot.lines())
+            .take_while(|(expected, actual)| expected == actual)
+            .count()
+            + 1;
+
+        assert!(
+            golden == snapshot,
+            "seed {}: generation differs from {:?} at line {}, run with UPDATE_GOLDEN=1 if intended",
+            seed,
+            path,
+            line
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use fxhash::hash32;
+
+use crate::components::sprite::sprite::Sprite;
+use crate::components::sprite::FrameSprites;
+use crate::graphics::texture;
+use crate::gun::{GunSpec, GunUpgrade};
+use crate::util::random::Random;
+use crate::util::rect::Rect;
+use crate::util::vector::Vec2;
+use crate::{vec2, Mat3};
+
+pub struct UpgradeBench {
+    position: Vec2,
+    upgrade: GunUpgrade,
+    seed: u32,
+}
+
+impl UpgradeBench {
+    pub fn new(position: Vec2, upgrade: GunUpgrade, seed: u32) -> Self {
+        Self {
+            position,
+            upgrade,
+            seed,
+        }
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    pub fn upgrade(&self) -> GunUpgrade {
+        self.upgrade
+    }
+
+    /// What this bench would turn `spec` into. The outcome only depends on the bench and the gun,
+    /// so that it can be previewed before buying, and changes once the gun has been upgraded.
+    pub fn upgraded(&self, spec: &GunSpec) -> Option<GunSpec> {
+        let mut random = Random::new(hash32(&(self.seed, spec.name())));
+        spec.upgraded(self.upgrade, &mut random)
+    }
+
+    pub fn draw(&self, timestamp: f64, sprites: &mut FrameSprites) {
+        let texture = match self.upgrade {
+            GunUpgrade::Enhance => texture::enhance_upgrade(),
+            GunUpgrade::Refine => texture::refine_upgrade(),
+            GunUpgrade::Reroll => texture::reroll_upgrade(),
+        };
+
+        let y = 0.21 + (timestamp * 1.5).sin() * 0.05;
+        let transform = Mat3::transform(vec2(0.0, y), vec2(0.3, 0.3));
+
+        let upgrade_sprite = Sprite::new_transformed(texture, transform);
+        sprites.draw_sprite(self.position, upgrade_sprite);
+
+        let sprite = Sprite::new_sized(texture::open_chest(), self.bounds().size);
+        sprites.draw_sprite(self.position, sprite);
+    }
+
+    pub fn bounds(&self) -> Rect {
+        Rect::focused(self.position, vec2(0.5, 0.5))
+    }
+}
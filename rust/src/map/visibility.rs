@@ -0,0 +1,173 @@
+use std::rc::Rc;
+
+use fxhash::{FxHashMap, FxHashSet};
+
+use crate::map::chunk::CHUNK_SIZE_I32;
+use crate::map::Map;
+use crate::util::coord::Coord;
+use crate::util::vector::Vec2;
+
+const MAX_CACHED_CHUNKS: usize = 16;
+
+/// Transforms from the first octant's `(column, row)` into each of the eight octants.
+const OCTANTS: [[i32; 4]; 8] = [
+    [1, 0, 0, 1],
+    [0, 1, 1, 0],
+    [0, -1, 1, 0],
+    [-1, 0, 0, 1],
+    [-1, 0, 0, -1],
+    [0, -1, -1, 0],
+    [0, 1, -1, 0],
+    [1, 0, 0, -1],
+];
+
+pub type VisibleTiles = Rc<FxHashSet<Coord>>;
+
+/// Computed fields of view, grouped by the chunk their origin lies in.
+#[derive(Default)]
+pub(super) struct VisibilityCache {
+    chunks: FxHashMap<Coord, FxHashMap<(Coord, i32), VisibleTiles>>,
+}
+
+impl VisibilityCache {
+    fn get(&self, origin: Coord, radius: i32) -> Option<VisibleTiles> {
+        self.chunks
+            .get(&Self::chunk(origin))
+            .and_then(|views| views.get(&(origin, radius)))
+            .cloned()
+    }
+
+    fn insert(&mut self, origin: Coord, radius: i32, tiles: VisibleTiles) {
+        let chunk = Self::chunk(origin);
+
+        if !self.chunks.contains_key(&chunk) && self.chunks.len() >= MAX_CACHED_CHUNKS {
+            self.chunks.clear();
+        }
+
+        self.chunks
+            .entry(chunk)
+            .or_default()
+            .insert((origin, radius), tiles);
+    }
+
+    /// Forgets every field of view that could have included the changed tile.
+    pub fn invalidate(&mut self, changed: Coord) {
+        for views in self.chunks.values_mut() {
+            views.retain(|(origin, radius), _| !is_in_radius(*origin, changed, *radius));
+        }
+
+        self.chunks.retain(|_, views| !views.is_empty());
+    }
+
+    fn chunk(coord: Coord) -> Coord {
+        let offset = coord.offset(CHUNK_SIZE_I32 / 2, CHUNK_SIZE_I32 / 2);
+        offset.chunk(CHUNK_SIZE_I32).0
+    }
+}
+
+impl Map {
+    /// Whether nothing but walkable tiles lies on the straight line between `from` and `to`.
+    pub fn line_of_sight(&self, from: Vec2, to: Vec2) -> bool {
+        let distance = from.distance(to);
+        self.raycast(from, to - from, distance).is_none()
+    }
+
+    /// Tiles within `radius` of `origin` that can be seen from its center, including the walls
+    /// bounding the view.
+    pub fn visible_tiles(&self, origin: Coord, radius: i32) -> VisibleTiles {
+        if let Some(tiles) = self.visibility.borrow().get(origin, radius) {
+            return tiles;
+        }
+
+        let mut visible = FxHashSet::default();
+        visible.insert(origin);
+
+        if self.at(origin).is_walkable() {
+            for octant in &OCTANTS {
+                self.cast_light(&mut visible, origin, radius, 1, 1.0, 0.0, octant);
+            }
+        }
+
+        let tiles = Rc::new(visible);
+        self.visibility
+            .borrow_mut()
+            .insert(origin, radius, tiles.clone());
+
+        tiles
+    }
+
+    /// Recursive shadowcasting of one octant, scanning rows outwards from `row` between the
+    /// `start` and `end` slopes.
+    #[allow(clippy::too_many_arguments)]
+    fn cast_light(
+        &self,
+        visible: &mut FxHashSet<Coord>,
+        origin: Coord,
+        radius: i32,
+        row: i32,
+        mut start: f64,
+        end: f64,
+        octant: &[i32; 4],
+    ) {
+        if start < end {
+            return;
+        }
+
+        let [xx, xy, yx, yy] = *octant;
+        let mut next_start = start;
+
+        for distance in row..=radius {
+            let dy = -distance;
+            let mut blocked = false;
+
+            for dx in -distance..=0 {
+                let left_slope = (dx as f64 - 0.5) / (dy as f64 + 0.5);
+                let right_slope = (dx as f64 + 0.5) / (dy as f64 - 0.5);
+
+                if start < right_slope {
+                    continue;
+                } else if end > left_slope {
+                    break;
+                }
+
+                let coord = origin.offset(dx * xx + dy * xy, dx * yx + dy * yy);
+
+                if is_in_radius(origin, coord, radius) {
+                    visible.insert(coord);
+                }
+
+                let opaque = !self.at(coord).is_walkable();
+
+                if blocked {
+                    if opaque {
+                        next_start = right_slope;
+                    } else {
+                        blocked = false;
+                        start = next_start;
+                    }
+                } else if opaque && distance < radius {
+                    blocked = true;
+                    self.cast_light(
+                        visible,
+                        origin,
+                        radius,
+                        distance + 1,
+                        start,
+                        left_slope,
+                        octant,
+                    );
+                    next_start = right_slope;
+                }
+            }
+
+            if blocked {
+                break;
+            }
+        }
+    }
+}
+
+fn is_in_radius(origin: Coord, coord: Coord, radius: i32) -> bool {
+    let offset = coord - origin;
+    offset.x() * offset.x() + offset.y() * offset.y() <= radius * radius
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+use crate::graphics::texture;
+use crate::util::random::Random;
+use crate::Mat3;
+
+const BASE_COST: usize = 1000;
+/// Added to the price of every perk for each perk the player already owns.
+const COST_INCREASE: usize = 750;
+
+pub const VITALITY_HEALTH: f64 = 50.0;
+pub const QUICK_RECOVERY_FACTOR: f64 = 0.5;
+pub const QUICK_RELOAD_FACTOR: f64 = 1.5;
+pub const SWIFTNESS_FACTOR: f64 = 1.25;
+
+/// A permanent upgrade bought from a perk station.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Perk {
+    /// Raises maximum health.
+    Vitality,
+    /// Starts regenerating health sooner and heals faster.
+    QuickRecovery,
+    /// Allows carrying another gun.
+    ExtraSlot,
+    /// Reloads faster.
+    QuickReload,
+    /// Moves faster.
+    Swiftness,
+}
+
+impl Perk {
+    const ALL: [Perk; 5] = [
+        Perk::Vitality,
+        Perk::QuickRecovery,
+        Perk::ExtraSlot,
+        Perk::QuickReload,
+        Perk::Swiftness,
+    ];
+
+    pub fn random(random: &mut Random) -> Self {
+        *random.element(&Self::ALL)
+    }
+
+    /// The price of a perk for a player already owning `owned` perks.
+    pub fn cost(owned: usize) -> usize {
+        BASE_COST + COST_INCREASE * owned
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Perk::Vitality => "Vitality",
+            Perk::QuickRecovery => "Quick Recovery",
+            Perk::ExtraSlot => "Extra Slot",
+            Perk::QuickReload => "Quick Reload",
+            Perk::Swiftness => "Swiftness",
+        }
+    }
+
+    pub fn texture(&self) -> Mat3 {
+        match self {
+            Perk::Vitality => texture::vitality_perk(),
+            Perk::QuickRecovery => texture::quick_recovery_perk(),
+            Perk::ExtraSlot => texture::extra_slot_perk(),
+            Perk::QuickReload => texture::quick_reload_perk(),
+            Perk::Swiftness => texture::swiftness_perk(),
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use std::time::Instant;
+
+use lazy_static::lazy_static;
+use log::Level;
+
+lazy_static! {
+    static ref START: Instant = Instant::now();
+}
+
+pub fn current_time() -> f64 {
+    START.elapsed().as_secs_f64() * 1000.0
+}
+
+pub fn intern(name: &'static str) -> &'static str {
+    name
+}
+
+pub fn play_sound(name: &str) {
+    log::trace!("Played sound {}", name);
+}
+
+pub fn write_log(level: Level, message: &str) {
+    eprintln!("[{}] {}", level, message);
+}
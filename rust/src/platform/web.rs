@@ -0,0 +1,41 @@
+use log::Level;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = window, js_name = dungeonDemonsPlaySound)]
+    fn dispatch_sound(name: &str);
+
+    #[wasm_bindgen(js_namespace = console, js_name = debug)]
+    fn console_debug(s: &str);
+
+    #[wasm_bindgen(js_namespace = console, js_name = info)]
+    fn console_info(s: &str);
+
+    #[wasm_bindgen(js_namespace = console, js_name = warn)]
+    fn console_warn(s: &str);
+
+    #[wasm_bindgen(js_namespace = console, js_name = error)]
+    fn console_error(s: &str);
+}
+
+pub fn current_time() -> f64 {
+    web_sys::window().unwrap().performance().unwrap().now()
+}
+
+pub fn intern(name: &'static str) -> &'static str {
+    wasm_bindgen::intern(name)
+}
+
+pub fn play_sound(name: &str) {
+    dispatch_sound(name);
+}
+
+pub fn write_log(level: Level, message: &str) {
+    match level {
+        Level::Error => console_error(message),
+        Level::Warn => console_warn(message),
+        Level::Info => console_info(message),
+        Level::Debug | Level::Trace => console_debug(message),
+    }
+}
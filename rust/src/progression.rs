@@ -1,14 +1,18 @@
 use float_ord::FloatOrd;
+use serde::{Deserialize, Serialize};
 use specs::prelude::*;
 use specs::{ReadExpect, System, WriteExpect};
 
 use crate::audio::{play_sound, sound};
 use crate::components::bounds::Bounds;
+use crate::components::elite::Elite;
+use crate::components::health::Health;
 use crate::components::player::Player;
-use crate::entities::demon;
+use crate::entities::boss;
+use crate::entities::demon::{self, DemonKind};
 use crate::game::Delta;
 use crate::map::Map;
-use crate::util::random::Random;
+use crate::util::random::{Random, RandomStreams};
 use crate::util::rect::Rect;
 use crate::util::vector::Vec2;
 use crate::vec2;
@@ -19,8 +23,22 @@ const SPAWNER_PATHFIND_THRESHOLD: f64 = 75.0;
 
 const IDEAL_SPAWN_DISTANCE: f64 = 8.0;
 
+/// Every this many rounds, the round is fought against a single boss and its minions.
+const BOSS_ROUND_INTERVAL: usize = 5;
+const BOSS_HEALTH_FACTOR: f64 = 15.0;
+const BOSS_SPEED_FACTOR: f64 = 0.6;
+
+const RANGED_FIRST_ROUND: usize = 3;
+const MAX_RANGED_CHANCE: f64 = 0.35;
+const RANGED_HEALTH_FACTOR: f64 = 0.6;
+
+/// Spawners this close to a player and in plain view are only used when no others are left.
+const SPAWN_VISIBILITY_RADIUS: i32 = 16;
+
 const DEFAULT_SPAWN_LOCATION: Vec2 = vec2(4.5, 0.5);
 
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Progression {
     round: usize,
     remaining_enemies: usize,
@@ -41,10 +59,27 @@ impl Progression {
     }
 
     pub fn report_enemy_death(&mut self) {
+        if self.is_boss_round() {
+            // Minions aren't respawned, so losing one is as good as killing it.
+            self.report_enemy_killed();
+            return;
+        }
+
         self.spawned_enemies -= 1;
         self.active_enemies -= 1;
     }
 
+    /// Counts enemies created outside of `SpawnEnemies`, such as a boss' minions, towards the
+    /// enemies left in the round.
+    pub fn report_enemies_added(&mut self, count: usize) {
+        self.remaining_enemies += count;
+        self.active_enemies += count;
+    }
+
+    pub fn is_boss_round(&self) -> bool {
+        (self.round + 1) % BOSS_ROUND_INTERVAL == 0
+    }
+
     pub fn report_enemy_killed(&mut self) {
         self.remaining_enemies -= 1;
         self.active_enemies -= 1;
@@ -62,7 +97,11 @@ impl Progression {
     }
 
     fn total_enemies(&self) -> usize {
-        Self::enemies_of_round(self.round)
+        if self.is_boss_round() {
+            1
+        } else {
+            Self::enemies_of_round(self.round)
+        }
     }
 
     fn concurrent_enemies(&self) -> usize {
@@ -88,13 +127,46 @@ impl Progression {
         (self.round as f64 + 5.0) / self.total_enemies() as f64 + start_bias
     }
 
-    fn enemy_health(&self) -> f64 {
+    /// How likely a spawned demon is ranged, starting at `RANGED_FIRST_ROUND` and growing towards
+    /// `MAX_RANGED_CHANCE`.
+    fn ranged_chance(&self) -> f64 {
+        if self.round < RANGED_FIRST_ROUND {
+            return 0.0;
+        }
+
+        let rounds = (self.round - RANGED_FIRST_ROUND) as f64;
+        (0.1 + rounds * 0.025).min(MAX_RANGED_CHANCE)
+    }
+
+    fn enemy_kind(&self, random: &mut Random) -> DemonKind {
+        let chance = self.ranged_chance();
+
+        if chance > 0.0 && random.next_f64() < chance {
+            DemonKind::Ranged
+        } else {
+            DemonKind::Melee
+        }
+    }
+
+    fn enemy_health(&self, kind: DemonKind) -> f64 {
         let base = self.round as f64 * 25.0 + 25.0;
         let multiplier = 1.025f64.powi(self.round as i32);
-        base * multiplier
+
+        match kind {
+            DemonKind::Melee => base * multiplier,
+            DemonKind::Ranged => base * multiplier * RANGED_HEALTH_FACTOR,
+        }
+    }
+
+    pub fn minion_health(&self) -> f64 {
+        self.enemy_health(DemonKind::Melee)
     }
 
-    fn enemy_speed(&self) -> f64 {
+    fn boss_health(&self) -> f64 {
+        self.enemy_health(DemonKind::Melee) * BOSS_HEALTH_FACTOR
+    }
+
+    pub fn enemy_speed(&self) -> f64 {
         let max = 3.0;
         let min = 1.0;
         max - (max - min) / (self.round as f64 / 2.5 + 1.0)
@@ -104,20 +176,20 @@ impl Progression {
 pub struct SpawnEnemies;
 
 impl SpawnEnemies {
-    fn find_spawn(map: &Map, player_positions: &[Vec2]) -> Vec2 {
+    fn find_spawn(map: &Map, random: &mut Random, player_positions: &[Vec2]) -> Vec2 {
         if player_positions.is_empty() {
             return DEFAULT_SPAWN_LOCATION;
         }
 
-        let player = *Random::global().element(&player_positions);
+        let player = *random.element(player_positions);
 
         let candidates = Self::find_spawns(map, player, &player_positions);
         if candidates.is_empty() {
             return DEFAULT_SPAWN_LOCATION;
         }
 
-        let candidate_index = Random::global()
-            .weighted_index(|| candidates.iter().map(|(_, weight)| weight).copied());
+        let candidate_index =
+            random.weighted_index(|| candidates.iter().map(|(_, weight)| weight).copied());
 
         candidates[candidate_index].0
     }
@@ -134,12 +206,24 @@ impl SpawnEnemies {
             .map(|position| position.coord())
             .collect::<Vec<_>>();
 
-        map.spawners_in(search)
+        let views = player_coords
+            .iter()
+            .map(|coord| map.visible_tiles(*coord, SPAWN_VISIBILITY_RADIUS))
+            .collect::<Vec<_>>();
+
+        let (hidden, visible): (Vec<_>, Vec<_>) = map
+            .spawners_in(search)
             .filter(|spawn| {
                 let targets = || player_coords.iter().copied();
                 map.pathfind(spawn.coord(), targets, SPAWNER_PATHFIND_THRESHOLD)
                     .is_some()
             })
+            .partition(|spawn| !views.iter().any(|view| view.contains(&spawn.coord())));
+
+        let spawners = if hidden.is_empty() { visible } else { hidden };
+
+        spawners
+            .into_iter()
             .map(|spawn| {
                 let closest = player_positions
                     .iter()
@@ -166,6 +250,7 @@ impl SpawnEnemies {
 pub struct SpawnEnemiesData<'a> {
     lazy_update: ReadExpect<'a, LazyUpdate>,
     map: ReadExpect<'a, Map>,
+    random: WriteExpect<'a, RandomStreams>,
     delta: ReadExpect<'a, Delta>,
     progression: WriteExpect<'a, Progression>,
     bounds: ReadStorage<'a, Bounds>,
@@ -193,7 +278,11 @@ impl<'a> System<'a> for SpawnEnemies {
         }
 
         if progression.spawned_enemies == 0 {
-            play_sound(sound::round_start());
+            if progression.is_boss_round() {
+                play_sound(sound::boss_round_start());
+            } else {
+                play_sound(sound::round_start());
+            }
         }
 
         progression.time_until_spawn += progression.spawn_delay();
@@ -205,13 +294,26 @@ impl<'a> System<'a> for SpawnEnemies {
             .map(|(bounds, _)| bounds.0.center())
             .collect::<Vec<_>>();
 
-        let spawn = Self::find_spawn(&data.map, &player_positions);
+        let spawn = Self::find_spawn(&data.map, data.random.ai(), &player_positions);
+
+        if progression.is_boss_round() {
+            let health = progression.boss_health();
+            let speed = progression.enemy_speed() * BOSS_SPEED_FACTOR;
+
+            data.lazy_update.exec_mut(move |world| {
+                boss::create(world, spawn, Health::full(health), speed);
+            });
+
+            return;
+        }
 
-        let health = progression.enemy_health();
-        let speed = progression.enemy_speed();
+        let kind = progression.enemy_kind(data.random.ai());
+        let elite = Elite::roll(progression.round, data.random.ai());
+        let health = progression.enemy_health(kind);
+        let speed = progression.enemy_speed() * elite.as_ref().map_or(1.0, Elite::speed_factor);
 
         data.lazy_update.exec_mut(move |world| {
-            demon::create_spawning(world, spawn, health, speed);
+            demon::create_spawning(world, kind, spawn, health, speed, elite);
         });
     }
 }
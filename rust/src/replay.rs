@@ -0,0 +1,273 @@
+use std::vec;
+
+use fxhash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::game::Game;
+use crate::input::{InputSource, Inputs};
+use crate::util::vector::vec2;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum InputEvent {
+    KeyDown {
+        #[serde(default)]
+        player: Option<usize>,
+        key: String,
+    },
+    KeyUp {
+        #[serde(default)]
+        player: Option<usize>,
+        key: String,
+    },
+    UpdateMouse {
+        #[serde(default)]
+        player: usize,
+        x: f64,
+        y: f64,
+    },
+    MouseDown {
+        #[serde(default)]
+        player: usize,
+        x: f64,
+        y: f64,
+    },
+    MouseUp {
+        #[serde(default)]
+        player: usize,
+        x: f64,
+        y: f64,
+    },
+    UpdateJoystick {
+        #[serde(default)]
+        player: usize,
+        x: f64,
+        y: f64,
+    },
+    JoystickTap {
+        #[serde(default)]
+        player: usize,
+    },
+    JoystickSwipe {
+        #[serde(default)]
+        player: usize,
+    },
+    AddPlayer {
+        source: InputSource,
+    },
+    LoadState {
+        state: String,
+    },
+    Tick {
+        delta: f64,
+    },
+}
+
+impl InputEvent {
+    pub fn apply(&self, game: &mut Game) {
+        match self {
+            InputEvent::KeyDown { player: None, key } => {
+                for inputs in game.all_inputs() {
+                    inputs.key_down(key.clone());
+                }
+            }
+            InputEvent::KeyDown {
+                player: Some(player),
+                key,
+            } => {
+                Self::apply_to_player(game, *player, |inputs| inputs.key_down(key.clone()));
+            }
+            InputEvent::KeyUp { player: None, key } => {
+                for inputs in game.all_inputs() {
+                    inputs.key_up(key.clone());
+                }
+            }
+            InputEvent::KeyUp {
+                player: Some(player),
+                key,
+            } => {
+                Self::apply_to_player(game, *player, |inputs| inputs.key_up(key.clone()));
+            }
+            InputEvent::UpdateMouse { player, x, y } => {
+                Self::apply_to_player(game, *player, |inputs| *inputs.mouse_mut() = vec2(*x, *y));
+            }
+            InputEvent::MouseDown { player, x, y } => {
+                Self::apply_to_player(game, *player, |inputs| inputs.mouse_down(vec2(*x, *y)));
+            }
+            InputEvent::MouseUp { player, x, y } => {
+                Self::apply_to_player(game, *player, |inputs| inputs.mouse_up(vec2(*x, *y)));
+            }
+            InputEvent::UpdateJoystick { player, x, y } => {
+                Self::apply_to_player(game, *player, |inputs| {
+                    *inputs.joystick_mut() = vec2(*x, *y)
+                });
+            }
+            InputEvent::JoystickTap { player } => {
+                Self::apply_to_player(game, *player, Inputs::joystick_tap);
+            }
+            InputEvent::JoystickSwipe { player } => {
+                Self::apply_to_player(game, *player, Inputs::joystick_swipe);
+            }
+            InputEvent::AddPlayer { source } => {
+                if game.add_player(*source).is_none() {
+                    log::warn!("Cannot add more local players");
+                }
+            }
+            InputEvent::LoadState { state } => {
+                if let Err(error) = game.load_state(state) {
+                    log::error!("Failed to load state: {}", error);
+                }
+            }
+            InputEvent::Tick { delta } => game.tick(*delta),
+        }
+    }
+
+    /// Routes a player input to the given local player, leaving other events untouched.
+    pub fn for_player(mut self, target: usize) -> Self {
+        match &mut self {
+            InputEvent::KeyDown { player, .. } | InputEvent::KeyUp { player, .. } => {
+                *player = Some(target)
+            }
+            InputEvent::UpdateMouse { player, .. }
+            | InputEvent::MouseDown { player, .. }
+            | InputEvent::MouseUp { player, .. }
+            | InputEvent::UpdateJoystick { player, .. }
+            | InputEvent::JoystickTap { player }
+            | InputEvent::JoystickSwipe { player } => *player = target,
+            InputEvent::AddPlayer { .. }
+            | InputEvent::LoadState { .. }
+            | InputEvent::Tick { .. } => {}
+        }
+
+        self
+    }
+
+    pub fn is_player_input(&self) -> bool {
+        !matches!(
+            self,
+            InputEvent::AddPlayer { .. } | InputEvent::LoadState { .. } | InputEvent::Tick { .. }
+        )
+    }
+
+    fn apply_to_player(game: &mut Game, player: usize, apply: impl FnOnce(&mut Inputs)) {
+        match game.inputs(player) {
+            Some(inputs) => apply(inputs),
+            None => log::warn!("Received input for unknown local player {}", player),
+        }
+    }
+
+    fn is_tick(&self) -> bool {
+        matches!(self, InputEvent::Tick { .. })
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recording {
+    seed: u32,
+    is_mobile: bool,
+    events: Vec<InputEvent>,
+    #[serde(skip)]
+    last_mouse: FxHashMap<usize, InputEvent>,
+    #[serde(skip)]
+    last_joystick: FxHashMap<usize, InputEvent>,
+}
+
+impl Recording {
+    pub fn new(seed: u32, is_mobile: bool) -> Self {
+        Self {
+            seed,
+            is_mobile,
+            events: Vec::new(),
+            last_mouse: FxHashMap::default(),
+            last_joystick: FxHashMap::default(),
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn record(&mut self, event: InputEvent) {
+        let (last, player) = match event {
+            InputEvent::UpdateMouse { player, .. } => (&mut self.last_mouse, player),
+            InputEvent::UpdateJoystick { player, .. } => (&mut self.last_joystick, player),
+            _ => {
+                self.events.push(event);
+                return;
+            }
+        };
+
+        if last.get(&player) == Some(&event) {
+            return;
+        }
+
+        last.insert(player, event.clone());
+        self.events.push(event);
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn is_mobile(&self) -> bool {
+        self.is_mobile
+    }
+
+    pub fn events(&self) -> &[InputEvent] {
+        &self.events
+    }
+
+    pub fn tick_count(&self) -> usize {
+        self.events.iter().filter(|event| event.is_tick()).count()
+    }
+}
+
+pub struct Replayer {
+    game: Game,
+    events: vec::IntoIter<InputEvent>,
+    ticks: usize,
+}
+
+impl Replayer {
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            game: Game::new(recording.seed, recording.is_mobile),
+            events: recording.events.into_iter(),
+            ticks: 0,
+        }
+    }
+
+    pub fn step(&mut self) -> bool {
+        for event in self.events.by_ref() {
+            event.apply(&mut self.game);
+
+            if event.is_tick() {
+                self.ticks += 1;
+                return true;
+            }
+        }
+
+        false
+    }
+
+    pub fn run_to_end(&mut self) {
+        while self.step() {}
+    }
+
+    pub fn ticks(&self) -> usize {
+        self.ticks
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn game_mut(&mut self) -> &mut Game {
+        &mut self.game
+    }
+}
@@ -0,0 +1,373 @@
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+use specs::{Entity, Join, World, WorldExt};
+
+use crate::components::boss::Boss;
+use crate::components::bounds::Bounds;
+use crate::components::elite::Elite;
+use crate::components::health::Health;
+use crate::components::lifetime::Lifetime;
+use crate::components::pickup::Pickup;
+use crate::components::player::Player;
+use crate::components::player_seeker::PlayerSeeker;
+use crate::components::ranged_attacker::RangedAttacker;
+use crate::components::regen::HealthRegen;
+use crate::components::spawning_demon::SpawningDemon;
+use crate::ecs::WorldExtensions;
+use crate::entities::boss;
+use crate::entities::demon::{self, DemonKind};
+use crate::entities::{pickup, player};
+use crate::game::{ControlledPlayers, Timestamp};
+use crate::gun::{GunSpec, GunSpecGenerator};
+use crate::input::InputSource;
+use crate::map::{Map, Tile};
+use crate::progression::Progression;
+use crate::util::coord::Coord;
+use crate::util::random::RandomStreams;
+use crate::util::rect::Rect;
+use crate::util::vector::Vec2;
+
+const VERSION: u32 = 4;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveState {
+    version: u32,
+    seed: u32,
+    timestamp: f64,
+    random: RandomStreams,
+    progression: Progression,
+    gun_spec_generator: GunSpecGenerator,
+    players: Vec<SavedPlayer>,
+    controlled_players: Vec<SavedControlledPlayer>,
+    demons: Vec<SavedDemon>,
+    spawning_demons: Vec<SavedSpawningDemon>,
+    #[serde(default)]
+    bosses: Vec<SavedBoss>,
+    #[serde(default)]
+    pickups: Vec<SavedPickup>,
+    map: SavedMap,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedPlayer {
+    bounds: Rect,
+    health: Health,
+    regen: HealthRegen,
+    player: Player,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedControlledPlayer {
+    source: InputSource,
+    player: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedDemon {
+    #[serde(default)]
+    kind: DemonKind,
+    bounds: Rect,
+    health: Health,
+    speed: f64,
+    #[serde(default)]
+    elite: Option<Elite>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedSpawningDemon {
+    #[serde(default)]
+    kind: DemonKind,
+    bounds: Rect,
+    health: Health,
+    speed: f64,
+    #[serde(default)]
+    elite: Option<Elite>,
+    remaining_time: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedBoss {
+    bounds: Rect,
+    health: Health,
+    speed: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedPickup {
+    position: Vec2,
+    pickup: Pickup,
+    remaining_time: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedMap {
+    changed_tiles: Vec<(Coord, Tile)>,
+    #[serde(default)]
+    dropped_chests: Vec<Vec2>,
+    opened_chests: Vec<SavedChest>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedChest {
+    position: Vec2,
+    gun: Rc<GunSpec>,
+    exhausted_players: Vec<usize>,
+}
+
+impl SaveState {
+    pub fn capture(world: &World) -> Self {
+        let entities = world.entities();
+        let bounds = world.read_storage::<Bounds>();
+        let healths = world.read_storage::<Health>();
+        let regens = world.read_storage::<HealthRegen>();
+        let players = world.read_storage::<Player>();
+        let seekers = world.read_storage::<PlayerSeeker>();
+        let ranged_attackers = world.read_storage::<RangedAttacker>();
+        let spawning_demons = world.read_storage::<SpawningDemon>();
+        let bosses = world.read_storage::<Boss>();
+        let elites = world.read_storage::<Elite>();
+        let pickups = world.read_storage::<Pickup>();
+        let lifetimes = world.read_storage::<Lifetime>();
+
+        let mut player_entities = Vec::new();
+        let mut saved_players = Vec::new();
+
+        for (entity, bounds, health, regen, player) in
+            (&entities, &bounds, &healths, &regens, &players).join()
+        {
+            player_entities.push(entity);
+            saved_players.push(SavedPlayer {
+                bounds: bounds.0,
+                health: *health,
+                regen: regen.clone(),
+                player: player.clone(),
+            });
+        }
+
+        let player_index = |entity: Entity| player_entities.iter().position(|e| *e == entity);
+
+        let demons = (
+            &bounds,
+            &healths,
+            &seekers,
+            ranged_attackers.maybe(),
+            elites.maybe(),
+            !&bosses,
+        )
+            .join()
+            .map(
+                |(bounds, health, seeker, ranged_attacker, elite, _)| SavedDemon {
+                    kind: match ranged_attacker {
+                        Some(_) => DemonKind::Ranged,
+                        None => DemonKind::Melee,
+                    },
+                    bounds: bounds.0,
+                    health: *health,
+                    speed: seeker.speed(),
+                    elite: elite.cloned(),
+                },
+            )
+            .collect();
+
+        let spawning_demons = (&bounds, &healths, &spawning_demons, elites.maybe())
+            .join()
+            .map(|(bounds, health, spawning, elite)| SavedSpawningDemon {
+                kind: spawning.kind(),
+                bounds: bounds.0,
+                health: *health,
+                speed: spawning.speed(),
+                elite: elite.cloned(),
+                remaining_time: spawning.remaining_time(),
+            })
+            .collect();
+
+        let saved_bosses = (&bounds, &healths, &seekers, &bosses)
+            .join()
+            .map(|(bounds, health, seeker, _)| SavedBoss {
+                bounds: bounds.0,
+                health: *health,
+                speed: seeker.speed(),
+            })
+            .collect();
+
+        let saved_pickups = (&bounds, &pickups, &lifetimes)
+            .join()
+            .map(|(bounds, pickup, lifetime)| SavedPickup {
+                position: bounds.0.center(),
+                pickup: *pickup,
+                remaining_time: lifetime.remaining_time(),
+            })
+            .collect();
+
+        let map = world.fetch::<Map>();
+
+        let opened_chests = map
+            .opened_chests()
+            .map(|chest| SavedChest {
+                position: chest.position(),
+                gun: Rc::new(chest.gun().unwrap().clone()),
+                exhausted_players: chest.exhausted_players().filter_map(player_index).collect(),
+            })
+            .collect();
+
+        let controlled_players = world
+            .fetch::<ControlledPlayers>()
+            .slots()
+            .map(|(entity, source)| SavedControlledPlayer {
+                source,
+                player: entity.and_then(player_index),
+            })
+            .collect();
+
+        Self {
+            version: VERSION,
+            seed: map.seed(),
+            timestamp: world.timestamp(),
+            random: (*world.fetch::<RandomStreams>()).clone(),
+            progression: (*world.fetch::<Progression>()).clone(),
+            gun_spec_generator: (*world.fetch::<GunSpecGenerator>()).clone(),
+            players: saved_players,
+            controlled_players,
+            demons,
+            spawning_demons,
+            bosses: saved_bosses,
+            pickups: saved_pickups,
+            map: SavedMap {
+                changed_tiles: map.changed_tiles().collect(),
+                dropped_chests: map.dropped_chests().collect(),
+                opened_chests,
+            },
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let state = serde_json::from_str::<Self>(json).map_err(|error| error.to_string())?;
+
+        if state.version != VERSION {
+            return Err(format!("unsupported save version {}", state.version));
+        }
+
+        Ok(state)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn restore(self, world: &mut World) {
+        world.fetch_mut::<Timestamp>().0 = self.timestamp;
+        *world.fetch_mut::<Progression>() = self.progression;
+        *world.fetch_mut::<GunSpecGenerator>() = self.gun_spec_generator;
+
+        let player_entities = self
+            .players
+            .into_iter()
+            .map(|saved| Self::restore_player(world, saved))
+            .collect::<Vec<_>>();
+
+        let mut controlled_players = ControlledPlayers::new();
+
+        for saved in self.controlled_players {
+            let entity = saved.player.map(|index| player_entities[index]);
+            let focus = entity
+                .map(|entity| world.unwrap_read::<Bounds>(entity).0.center())
+                .unwrap_or_else(Vec2::zero);
+
+            controlled_players.push(entity, saved.source, focus);
+        }
+
+        world.insert(controlled_players);
+
+        for saved in self.demons {
+            let entity = demon::create(
+                world,
+                saved.kind,
+                saved.bounds.center(),
+                saved.health,
+                saved.speed,
+                saved.elite,
+            );
+            world.unwrap_write::<Bounds>(entity).0 = saved.bounds;
+        }
+
+        for saved in self.spawning_demons {
+            let entity = demon::create_spawning_timed(
+                world,
+                saved.kind,
+                saved.bounds.center(),
+                saved.health,
+                saved.speed,
+                saved.elite,
+                saved.remaining_time,
+            );
+
+            world.unwrap_write::<Bounds>(entity).0 = saved.bounds;
+        }
+
+        for saved in self.bosses {
+            let entity = boss::create(world, saved.bounds.center(), saved.health, saved.speed);
+            world.unwrap_write::<Bounds>(entity).0 = saved.bounds;
+        }
+
+        for saved in self.pickups {
+            pickup::create_timed(world, saved.position, saved.pickup, saved.remaining_time);
+        }
+
+        let mut map = world.fetch_mut::<Map>();
+
+        for (coord, tile) in self.map.changed_tiles {
+            map.set(coord, tile);
+        }
+
+        for position in self.map.dropped_chests {
+            map.drop_chest(position);
+        }
+
+        for saved in self.map.opened_chests {
+            let search = Rect::focused(saved.position, Vec2::one());
+            let chest = map
+                .chests_in_mut(search)
+                .find(|chest| chest.position() == saved.position);
+
+            match chest {
+                Some(chest) => {
+                    let exhausted_players = saved
+                        .exhausted_players
+                        .iter()
+                        .filter_map(|index| player_entities.get(*index))
+                        .copied();
+
+                    chest.restore(saved.gun, exhausted_players);
+                }
+                None => log::warn!("No chest found at {:?} while loading", saved.position),
+            }
+        }
+
+        *world.fetch_mut::<RandomStreams>() = self.random;
+    }
+
+    fn restore_player(world: &mut World, saved: SavedPlayer) -> Entity {
+        let entity = player::create(world, saved.bounds.center());
+
+        world.unwrap_write::<Bounds>(entity).0 = saved.bounds;
+        *world.unwrap_write::<Health>(entity) = saved.health;
+        *world.unwrap_write::<HealthRegen>(entity) = saved.regen;
+        *world.unwrap_write::<Player>(entity) = saved.player;
+
+        entity
+    }
+}
@@ -1,6 +1,7 @@
 use crate::util::rect::{rect, Rect};
 use crate::util::vector::Vec2;
 use crate::vec2;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::fmt::{Debug, Formatter};
 use std::ops::{Add, AddAssign, Mul, MulAssign, Sub, SubAssign};
@@ -9,7 +10,7 @@ pub const fn coord(x: i32, y: i32) -> Coord {
     Coord { x, y }
 }
 
-#[derive(Copy, Clone, Hash, Eq, PartialEq)]
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Coord {
     x: i32,
     y: i32,
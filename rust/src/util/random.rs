@@ -1,10 +1,9 @@
 use std::collections::Bound;
 use std::hash::Hasher;
 use std::ops::{Range, RangeBounds};
-use std::sync::{Mutex, MutexGuard};
 
-use fxhash::FxHasher32;
-use lazy_static::lazy_static;
+use fxhash::{hash32, FxHasher32};
+use serde::{Deserialize, Serialize};
 
 use crate::util::vector::{Vec2, Vector};
 use crate::vec2;
@@ -24,20 +23,52 @@ pub fn hash32_vec<const N: usize>(vector: Vector<N>) -> u32 {
     hasher.finish() as u32
 }
 
-lazy_static! {
-    static ref GLOBAL_RANDOM: Mutex<Random> = Mutex::new(Random::new(0));
+/// Independent generators for each kind of randomness, so that drawing from one never shifts the
+/// sequence of another.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RandomStreams {
+    ai: Random,
+    loot: Random,
+    combat: Random,
+    cosmetic: Random,
 }
 
-#[derive(Clone)]
+impl RandomStreams {
+    pub fn new(seed: u32) -> Self {
+        let stream = |name: &str| Random::new(hash32(&(seed, name)));
+
+        Self {
+            ai: stream("ai"),
+            loot: stream("loot"),
+            combat: stream("combat"),
+            cosmetic: stream("cosmetic"),
+        }
+    }
+
+    pub fn ai(&mut self) -> &mut Random {
+        &mut self.ai
+    }
+
+    pub fn loot(&mut self) -> &mut Random {
+        &mut self.loot
+    }
+
+    pub fn combat(&mut self) -> &mut Random {
+        &mut self.combat
+    }
+
+    pub fn cosmetic(&mut self) -> &mut Random {
+        &mut self.cosmetic
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Random {
     state: u32,
 }
 
 impl Random {
-    pub fn global() -> MutexGuard<'static, Random> {
-        GLOBAL_RANDOM.lock().unwrap()
-    }
-
     pub fn new(state: u32) -> Self {
         Self { state }
     }
@@ -1,4 +1,5 @@
 use crate::{vec2, Mat3};
+use serde::{Deserialize, Serialize};
 use std::ops::{Add, AddAssign, Sub, SubAssign};
 
 use crate::util::coord::Coord;
@@ -8,7 +9,7 @@ pub fn rect(position: Vec2, size: Vec2) -> Rect {
     Rect { position, size }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Rect {
     pub position: Vec2,
     pub size: Vec2,
@@ -97,6 +98,13 @@ impl Rect {
         }
     }
 
+    pub fn interpolate(self, target: Self, alpha: f64) -> Self {
+        Self {
+            position: self.position + (target.position - self.position) * alpha,
+            size: self.size + (target.size - self.size) * alpha,
+        }
+    }
+
     pub fn expand(&self, amount: f64) -> Self {
         Self {
             position: self.position - amount,
@@ -104,6 +112,40 @@ impl Rect {
         }
     }
 
+    /// Distance along `direction` at which a ray from `origin` enters the rect, or zero if it
+    /// starts inside.
+    pub fn raycast(&self, origin: Vec2, direction: Vec2) -> Option<f64> {
+        let mut near: f64 = 0.0;
+        let mut far = f64::INFINITY;
+
+        let axes = [
+            (origin.x(), direction.x(), self.min().x(), self.max().x()),
+            (origin.y(), direction.y(), self.min().y(), self.max().y()),
+        ];
+
+        for (origin, direction, min, max) in axes {
+            if direction == 0.0 {
+                if origin < min || origin > max {
+                    return None;
+                }
+
+                continue;
+            }
+
+            let first = (min - origin) / direction;
+            let second = (max - origin) / direction;
+
+            near = near.max(first.min(second));
+            far = far.min(first.max(second));
+        }
+
+        if near <= far {
+            Some(near)
+        } else {
+            None
+        }
+    }
+
     pub fn coords(&self) -> impl Iterator<Item = Coord> {
         let min = self.min().coord();
         let max = self.max().coord();
@@ -1,5 +1,7 @@
 use crate::util::coord::{coord, Coord};
 use crate::Mat3;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
 use std::fmt::{Debug, Formatter};
 use std::ops::{
@@ -391,3 +393,22 @@ impl<const LENGTH: usize> Debug for Vector<LENGTH> {
         f.debug_list().entries(&self.components).finish()
     }
 }
+
+impl<const LENGTH: usize> Serialize for Vector<LENGTH> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.components)
+    }
+}
+
+impl<'de, const LENGTH: usize> Deserialize<'de> for Vector<LENGTH> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let components = Vec::<f64>::deserialize(deserializer)?;
+        let length = components.len();
+
+        let components = components
+            .try_into()
+            .map_err(|_| D::Error::invalid_length(length, &"a vector component for each axis"))?;
+
+        Ok(Self { components })
+    }
+}
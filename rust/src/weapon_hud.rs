@@ -1,6 +1,6 @@
 use crate::camera::Camera;
 use crate::components::player::Player;
-use crate::ecs::ReadControlledPlayerStorage;
+use crate::ecs::ReadControlledPlayersStorage;
 use crate::game::HudEnabled;
 use crate::graphics::{texture, DrawBuffer, EntityRendererSettings};
 use crate::util::vector::Vec2;
@@ -11,6 +11,7 @@ use specs::{System, WriteExpect};
 const BASE_SIZE: f64 = 0.25;
 const BASE_GAP: f64 = -0.05;
 const MARGIN: f64 = 0.03;
+const BACKGROUND_WIDTH: f64 = 1.3;
 
 pub struct DrawWeaponHud;
 
@@ -19,7 +20,7 @@ pub struct DrawWeaponHudData<'a> {
     camera: ReadExpect<'a, Camera>,
     buffer: WriteExpect<'a, DrawBuffer>,
     hud_enabled: ReadExpect<'a, HudEnabled>,
-    player: ReadControlledPlayerStorage<'a, Player>,
+    players: ReadControlledPlayersStorage<'a, Player>,
 }
 
 impl<'a> System<'a> for DrawWeaponHud {
@@ -30,24 +31,30 @@ impl<'a> System<'a> for DrawWeaponHud {
             return;
         }
 
-        let player = match data.player.value() {
-            Some(player) => player,
-            None => return,
-        };
+        for (index, player) in data.players.values() {
+            Self::draw_player_hud(&data.camera, &mut data.buffer, index, player);
+        }
+    }
+}
 
+impl DrawWeaponHud {
+    fn draw_player_hud(camera: &Camera, buffer: &mut DrawBuffer, index: usize, player: &Player) {
         let guns = player.guns();
 
-        let base_y = 0.2 * data.camera.vmin_ratio() + MARGIN - 1.0;
-        let size = BASE_SIZE * data.camera.vmin_ratio();
-        let gap = BASE_GAP * data.camera.vmin_ratio();
+        let base_y = 0.2 * camera.vmin_ratio() + MARGIN - 1.0;
+        let size = BASE_SIZE * camera.vmin_ratio();
+        let gap = BASE_GAP * camera.vmin_ratio();
+
+        let column_width = size * BACKGROUND_WIDTH + MARGIN;
+        let base_x = camera.aspect_ratio() - size / 2.0 - MARGIN - column_width * index as f64;
 
         for (i, gun) in guns.iter().enumerate() {
             let y = (size + gap) * (guns.len() - i - 1) as f64 + base_y;
 
             let scale = Vec2::one() * size;
-            let translation = vec2(data.camera.aspect_ratio() - size / 2.0 - MARGIN, y);
+            let translation = vec2(base_x, y);
 
-            let inverse_view = data.camera.view().inverse();
+            let inverse_view = camera.view().inverse();
             let transform = inverse_view * Mat3::transform(translation, scale);
 
             let background = if i == player.selected_gun_index() {
@@ -56,12 +63,12 @@ impl<'a> System<'a> for DrawWeaponHud {
                 texture::gun_hud_background_inactive()
             };
 
-            data.buffer.push(EntityRendererSettings {
-                transform: transform * Mat3::scale(vec2(1.3, 1.0)),
+            buffer.push(EntityRendererSettings {
+                transform: transform * Mat3::scale(vec2(BACKGROUND_WIDTH, 1.0)),
                 uv_transform: background,
             });
 
-            data.buffer.push(EntityRendererSettings {
+            buffer.push(EntityRendererSettings {
                 transform,
                 uv_transform: gun.spec().texture(),
             });
@@ -179,7 +179,7 @@ impl<'a> System<'a> for DrawHeldWeapons {
             .join();
 
         for (bounds, animation, facing, player) in iter {
-            if !player.is_mouse_down() || player.selected_gun().current_ammo() == 0 {
+            if player.is_down() || !player.is_mouse_down() || !player.selected_gun().can_fire() {
                 continue;
             }
 
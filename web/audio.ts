@@ -28,14 +28,18 @@ const SOUNDS: Record<string, Sound> = {
   shoot_fast: new Sound(require("/assets/shoot-fast.mp3")),
   shoot_sniper: new Sound(require("/assets/shoot-sniper.mp3")),
   shoot_shotgun: new Sound(require("/assets/shoot-shotgun.mp3")),
+  explosion: new Sound(require("/assets/shoot-3.mp3")),
   hit: new Sound(require("/assets/monster-hit.mp3")),
   kill: new Sound(require("/assets/monster-death.mp3")),
   player_hit: new Sound(require("/assets/monster-attack-2.mp3")),
   death: new Sound(require("/assets/down.mp3")),
   round_start: new Sound(require("/assets/round-start.mp3")),
+  boss_round_start: new Sound(require("/assets/grunt.mp3")),
   round_end: new Sound(require("/assets/round-end.mp3")),
   purchase: new Sound(require("/assets/purchase.mp3")),
   pickup: new Sound(require("/assets/pickup.mp3")),
+  dash: new Sound(require("/assets/dash.wav")),
+  revive: new Sound(require("/assets/round-start.mp3")),
 };
 
 async function playSound(name: string) {
@@ -100,6 +100,7 @@ export default class Graphics {
       state.entityShader.getUniformLocation("transform");
     const textureTransformLocation =
       state.entityShader.getUniformLocation("textureTransform");
+    const tintLocation = state.entityShader.getUniformLocation("tint");
 
     for (let i = 0; i < buffer.size; i++) {
       Program.uniformMat3(
@@ -114,6 +115,8 @@ export default class Graphics {
         buffer.readMatrix()
       );
 
+      Program.uniformVec3(state.context, tintLocation, buffer.readVec3());
+
       gl.drawArrays(gl.TRIANGLES, 0, 6);
     }
   }
@@ -3,10 +3,11 @@
 precision highp float;
 
 uniform sampler2D sampler;
+uniform vec3 tint;
 
 in vec2 uv;
 out vec4 color;
 
 void main() {
-    color = texture(sampler, uv);
+    color = texture(sampler, uv) * vec4(tint, 1.0);
 }
\ No newline at end of file
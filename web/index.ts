@@ -10,9 +10,11 @@ import { all } from "./util";
 import isMobileDetected from "./detect-mobile";
 
 const TAP_TIME = 150;
+const SWIPE_DISTANCE = 0.6;
 const MAX_TICK_DELTA = 0.05;
 const MAX_TICKS_PER_FRAME = 2;
 const PERF_REPORT_INTERVAL = 5;
+const SAVE_KEY = "dungeonDemonsSave";
 
 const joystickElement = document.getElementById("joystick")!!;
 const welcomeElement = document.getElementById("welcome")!!;
@@ -24,6 +26,14 @@ const creditsElement = document.getElementById("credits-display")!!;
 const interactHeadingElement = document.getElementById("interact-heading")!!;
 const interactCaptionElement = document.getElementById("interact-caption")!!;
 const ammoElement = document.getElementById("ammo-display")!!;
+const reloadElement = document.getElementById("reload-bar")!!;
+const reloadProgressElement = document.getElementById("reload-progress")!!;
+const bossBarElement = document.getElementById("boss-bar")!!;
+const bossHealthElement = document.getElementById("boss-health")!!;
+const dashBarElement = document.getElementById("dash-bar")!!;
+const dashCooldownElement = document.getElementById("dash-cooldown")!!;
+const bleedOutElement = document.getElementById("bleed-out-bar")!!;
+const bleedOutProgressElement = document.getElementById("bleed-out-progress")!!;
 
 const isMobile = isMobileDetected();
 
@@ -133,6 +143,7 @@ class Game {
   private mouseY = 0;
 
   private joystickTouchStart: number | null = null;
+  private joystickLastOffset = new Vec2(0, 0);
 
   private transitioning = false;
 
@@ -141,9 +152,11 @@ class Game {
     console.info(`Using seed ${seed}`);
 
     this.backend = new Wasm.Backend(seed, isMobile);
+    this.loadSave();
 
     this.addInputListeners();
     window.addEventListener("beforeunload", this.navigateListener);
+    document.addEventListener("visibilitychange", this.visibilityListener);
     this.performanceReportTask = this.addPerformanceReporter();
 
     this.loop();
@@ -155,7 +168,34 @@ class Game {
     this.playing = true;
   }
 
+  private loadSave(): void {
+    const save = localStorage.getItem(SAVE_KEY);
+    if (save === null) {
+      return;
+    }
+
+    if (this.backend.loadState(save)) {
+      console.info("Resumed saved game");
+    } else {
+      localStorage.removeItem(SAVE_KEY);
+    }
+  }
+
+  private writeSave(): void {
+    if (this.playing && !this.backend.isOver()) {
+      localStorage.setItem(SAVE_KEY, this.backend.saveState());
+    }
+  }
+
+  private readonly visibilityListener = () => {
+    if (document.hidden) {
+      this.writeSave();
+    }
+  };
+
   private readonly navigateListener = (event: Event) => {
+    this.writeSave();
+
     const playing =
       this.playTime !== null && performance.now() > this.playTime + 30_000;
     if (playing && !this.backend.isOver()) {
@@ -217,7 +257,11 @@ class Game {
           if (this.joystickTouchStart !== null) {
             console.log(performance.now() - this.joystickTouchStart);
             if (performance.now() - this.joystickTouchStart <= TAP_TIME) {
-              this.backend.joystickTap();
+              if (this.joystickLastOffset.length() >= SWIPE_DISTANCE) {
+                this.backend.joystickSwipe();
+              } else {
+                this.backend.joystickTap();
+              }
             }
           }
 
@@ -232,6 +276,7 @@ class Game {
       if (isJoystickTouch(touch)) {
         const offset = this.joystickOffset(touch);
         this.backend.updateJoystick(offset.x, offset.y);
+        this.joystickLastOffset = offset;
       } else {
         this.mouseX = touch.clientX;
         this.mouseY = touch.clientY;
@@ -361,6 +406,7 @@ class Game {
     }
 
     this.transitioning = true;
+    localStorage.removeItem(SAVE_KEY);
 
     transitionScreenElement.style.opacity = "1";
 
@@ -417,8 +463,40 @@ class Game {
     this.writeUiText(interactCaption, interactCaptionElement);
 
     const currentAmmo = this.backend.currentAmmo();
-    const maxAmmo = this.backend.maxAmmo();
-    ammoElement.innerText = `${currentAmmo}|${maxAmmo}`;
+    const reserveAmmo = this.backend.reserveAmmo();
+    ammoElement.innerText = `${currentAmmo}|${reserveAmmo}`;
+
+    const reloadProgress = this.backend.reloadProgress();
+    if (reloadProgress === undefined) {
+      reloadElement.style.visibility = "hidden";
+    } else {
+      reloadElement.style.visibility = "";
+      reloadProgressElement.style.width = `${reloadProgress * 100}%`;
+    }
+
+    const bossHealth = this.backend.bossHealth();
+    if (bossHealth === undefined) {
+      bossBarElement.style.visibility = "hidden";
+    } else {
+      bossBarElement.style.visibility = "";
+      bossHealthElement.style.width = `${bossHealth * 100}%`;
+    }
+
+    const dashCooldown = this.backend.dashCooldown();
+    if (dashCooldown === 0) {
+      dashBarElement.style.visibility = "hidden";
+    } else {
+      dashBarElement.style.visibility = "";
+      dashCooldownElement.style.width = `${(1 - dashCooldown) * 100}%`;
+    }
+
+    const bleedOut = this.backend.bleedOut();
+    if (bleedOut === undefined) {
+      bleedOutElement.style.visibility = "hidden";
+    } else {
+      bleedOutElement.style.visibility = "";
+      bleedOutProgressElement.style.width = `${bleedOut * 100}%`;
+    }
   }
 
   private writeUiText(uiText: [string, string][], element: HTMLElement): void {
@@ -444,11 +522,20 @@ class Game {
     }
   }
 
+  recording(): string {
+    return this.backend.recording();
+  }
+
   dispose(): void {
     all(
       () => this.cancelLoop(),
       () => this.removeInputListeners(),
       () => window.removeEventListener("beforeunload", this.navigateListener),
+      () =>
+        document.removeEventListener(
+          "visibilitychange",
+          this.visibilityListener
+        ),
       () => this.cancelPerformanceReporter(),
       () => this.backend.free(),
       () => this.graphics.dispose()
@@ -456,5 +543,14 @@ class Game {
   }
 }
 
+(window as any).dungeonDemonsRecording = () => game.recording();
+
+const gunCatalog = await fetch(require("/assets/guns.json")).then((resp) =>
+  resp.text()
+);
+
 Wasm.start();
+if (!Wasm.Backend.loadGunCatalog(gunCatalog)) {
+  console.warn("Using built-in gun catalog");
+}
 game = new Game();
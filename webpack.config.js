@@ -58,10 +58,15 @@ module.exports = {
         },
       },
       {
-        test: /\.(png|mp3)$/,
+        test: /\.(png|mp3|wav)$/,
         exclude: /node_modules/,
         type: "asset/resource",
       },
+      {
+        test: /\.json$/,
+        include: path.resolve(__dirname, "assets"),
+        type: "asset/resource",
+      },
     ],
   },
 };